@@ -0,0 +1,109 @@
+use {
+    std::{
+        env,
+        io::{self, IsTerminal},
+        str::FromStr,
+    },
+    tinyc_diagnostics::{Diagnostic, JsonDiagnostic, Renderer, SourceFile},
+};
+
+/// A source file read from disk or stdin.
+#[derive(Debug)]
+pub struct Source {
+    /// The path as given, or `<stdin>`.
+    pub name: String,
+    pub text: String,
+}
+
+/// When to color diagnostics.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ColorChoice {
+    /// Color if stderr is a terminal and `NO_COLOR` is not set.
+    Auto,
+    Always,
+    Never,
+}
+
+/// How to print diagnostics.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum MessageFormat {
+    /// Rendered with source snippets.
+    Human,
+    /// One JSON object per line.
+    Json,
+}
+
+impl ColorChoice {
+    pub const NAMES: &'static [&'static str] = &["auto", "always", "never"];
+}
+
+impl MessageFormat {
+    pub const NAMES: &'static [&'static str] = &["human", "json"];
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("unknown color choice `{}`", s)),
+        }
+    }
+}
+
+impl FromStr for MessageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            _ => Err(format!("unknown message format `{}`", s)),
+        }
+    }
+}
+
+/// Prints diagnostics to stderr.
+#[derive(Debug)]
+pub struct Reporter {
+    format: MessageFormat,
+    renderer: Renderer,
+}
+
+impl Reporter {
+    pub fn new(color: ColorChoice, format: MessageFormat) -> Self {
+        let color = match color {
+            ColorChoice::Auto => io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        };
+        Reporter {
+            format,
+            renderer: if color {
+                Renderer::colored()
+            } else {
+                Renderer::plain()
+            },
+        }
+    }
+
+    pub fn report(&self, source: &Source, diagnostics: &[Diagnostic]) {
+        let source = SourceFile::new(&source.name, &source.text);
+        for (i, diagnostic) in diagnostics.iter().enumerate() {
+            match self.format {
+                MessageFormat::Human => {
+                    if i != 0 {
+                        eprintln!();
+                    }
+                    eprint!("{}", self.renderer.render(diagnostic, &source));
+                }
+                MessageFormat::Json => {
+                    eprintln!("{}", JsonDiagnostic::new(diagnostic, &source).to_json());
+                }
+            }
+        }
+    }
+}
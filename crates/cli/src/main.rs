@@ -0,0 +1,243 @@
+//! The `tinyc` command-line driver.
+//!
+//! Every subcommand reads a program from a file, or from stdin if the file is `-` or omitted.
+//! Structured output goes to stdout in the conformance crate's stock formats;
+//! diagnostics go to stderr, and make the process exit with a nonzero status.
+
+use {
+    crate::diagnostics::{ColorChoice, MessageFormat, Reporter, Source},
+    conformance::format::{Format, Stock},
+    std::{
+        error::Error,
+        fs,
+        io::{self, Read},
+        path::PathBuf,
+        process,
+    },
+    structopt::StructOpt,
+    tinyc_diagnostics::{parse_diagnostics, Diagnostic},
+    tinyc_grammar::{
+        ast::{self, AstNode},
+        syntax::Tree,
+    },
+    tinyc_interpreter::{compile, execute, run, RuntimeError},
+    tinyc_parser::parse_text,
+};
+
+mod diagnostics;
+
+/// Tools for the Tiny-C language.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "tinyc")]
+struct Opt {
+    /// When to color diagnostics.
+    #[structopt(long, global = true, default_value = "auto", possible_values = ColorChoice::NAMES)]
+    color: ColorChoice,
+    /// How to print diagnostics.
+    #[structopt(
+        long,
+        global = true,
+        default_value = "human",
+        possible_values = MessageFormat::NAMES
+    )]
+    message_format: MessageFormat,
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Print the tokens of a program.
+    Lex {
+        #[structopt(flatten)]
+        input: Input,
+        #[structopt(flatten)]
+        output: Output,
+        /// Print the byte range of each token instead of its length.
+        #[structopt(long)]
+        spans: bool,
+    },
+    /// Print the lossless syntax tree of a program.
+    Parse {
+        #[structopt(flatten)]
+        input: Input,
+        #[structopt(flatten)]
+        output: Output,
+    },
+    /// Check a program for errors without running it.
+    Check {
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Run a program and print its final nonzero variables.
+    Run {
+        #[structopt(flatten)]
+        input: Input,
+        #[structopt(flatten)]
+        output: Output,
+        /// Compile to bytecode and execute it instead of interpreting the syntax tree.
+        #[structopt(long)]
+        vm: bool,
+    },
+    /// Compile a program and print its bytecode disassembly.
+    Compile {
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Print a program in the canonical style.
+    Fmt {
+        #[structopt(flatten)]
+        input: Input,
+        /// Print nothing, but fail if the program is not already formatted.
+        #[structopt(long)]
+        check: bool,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+struct Input {
+    /// The file to read the program from; `-` for stdin.
+    #[structopt(parse(from_os_str), default_value = "-")]
+    file: PathBuf,
+}
+
+#[derive(Debug, StructOpt)]
+struct Output {
+    /// The format to print structured output in.
+    #[structopt(long, short, default_value = "yaml", possible_values = Stock::NAMES)]
+    format: Stock,
+}
+
+/// Why a command did not succeed.
+enum Failure {
+    /// Diagnostics for the program were printed.
+    Diagnostics,
+    /// The command itself failed.
+    Error(Box<dyn Error>),
+}
+
+impl<E: Into<Box<dyn Error>>> From<E> for Failure {
+    fn from(error: E) -> Self {
+        Failure::Error(error.into())
+    }
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    let reporter = Reporter::new(opt.color, opt.message_format);
+    match execute_command(opt.command, &reporter) {
+        Ok(()) => {}
+        Err(Failure::Diagnostics) => process::exit(1),
+        Err(Failure::Error(error)) => {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+    }
+}
+
+fn execute_command(command: Command, reporter: &Reporter) -> Result<(), Failure> {
+    match command {
+        Command::Lex {
+            input,
+            output,
+            spans,
+        } => {
+            let source = input.read()?;
+            let out = if spans {
+                let tokens: Vec<_> = tinyc_lexer::tokenize_spanned(&source.text).collect();
+                output.format.serialize(&tokens)?
+            } else {
+                let tokens: Vec<_> = tinyc_lexer::tokenize(&source.text).collect();
+                output.format.serialize(&tokens)?
+            };
+            println!("{}", out);
+        }
+        Command::Parse { input, output } => {
+            let source = input.read()?;
+            let parse = parse_text(&source.text);
+            println!("{}", output.format.serialize(&Tree(parse.syntax().into()))?);
+            check_diagnostics(reporter, &source, parse_diagnostics(&parse))?;
+        }
+        Command::Check { input } => {
+            let source = input.read()?;
+            let program = parse_program(reporter, &source)?;
+            compile(&program).map_err(|error| runtime_error(reporter, &source, &error))?;
+        }
+        Command::Run { input, output, vm } => {
+            let source = input.read()?;
+            let program = parse_program(reporter, &source)?;
+            let variables = if vm {
+                let bytecode =
+                    compile(&program).map_err(|error| runtime_error(reporter, &source, &error))?;
+                execute(&bytecode)?
+            } else {
+                run(&program).map_err(|error| runtime_error(reporter, &source, &error))?
+            };
+            println!("{}", output.format.serialize(&variables)?);
+        }
+        Command::Compile { input } => {
+            let source = input.read()?;
+            let program = parse_program(reporter, &source)?;
+            let bytecode =
+                compile(&program).map_err(|error| runtime_error(reporter, &source, &error))?;
+            print!("{}", bytecode);
+        }
+        Command::Fmt { input, check } => {
+            let source = input.read()?;
+            let parse = parse_text(&source.text);
+            check_diagnostics(reporter, &source, parse_diagnostics(&parse))?;
+            let formatted = tinyc_fmt::format(&parse.syntax());
+            if !check {
+                print!("{}", formatted);
+            } else if formatted != source.text {
+                let error = format!("{} is not formatted", source.name);
+                return Err(Failure::Error(error.into()));
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Input {
+    fn read(&self) -> io::Result<Source> {
+        if self.file.as_os_str() == "-" {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            Ok(Source {
+                name: "<stdin>".to_string(),
+                text,
+            })
+        } else {
+            Ok(Source {
+                name: self.file.display().to_string(),
+                text: fs::read_to_string(&self.file)?,
+            })
+        }
+    }
+}
+
+/// Report diagnostics, failing if there are any.
+fn check_diagnostics(
+    reporter: &Reporter,
+    source: &Source,
+    diagnostics: Vec<Diagnostic>,
+) -> Result<(), Failure> {
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        reporter.report(source, &diagnostics);
+        Err(Failure::Diagnostics)
+    }
+}
+
+fn runtime_error(reporter: &Reporter, source: &Source, error: &RuntimeError) -> Failure {
+    reporter.report(source, &[error.into()]);
+    Failure::Diagnostics
+}
+
+/// Parse a program, failing if it has syntax errors.
+fn parse_program(reporter: &Reporter, source: &Source) -> Result<ast::Program, Failure> {
+    let parse = parse_text(&source.text);
+    check_diagnostics(reporter, source, parse_diagnostics(&parse))?;
+    Ok(ast::Program::cast(parse.syntax()).unwrap())
+}
@@ -0,0 +1,151 @@
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+fn tinyc(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tinyc"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> &str {
+    std::str::from_utf8(&output.stderr).unwrap()
+}
+
+#[test]
+fn lex() {
+    let output = tinyc(&["lex", "--format", "json"], "a=1;");
+    assert!(output.status.success());
+    let tokens: json::Value = json::from_str(stdout(&output)).unwrap();
+    assert_eq!(
+        tokens,
+        json::json!([{"Identifier": 1}, {"EqualsSign": 1}, {"Integer": 1}, {"Semicolon": 1}]),
+    );
+}
+
+#[test]
+fn parse() {
+    let output = tinyc(&["parse", "-"], "a=1;");
+    assert!(output.status.success());
+    assert!(stdout(&output).starts_with("Program:\n  - StatementExpression:"));
+}
+
+#[test]
+fn run() {
+    for args in &[&["run"][..], &["run", "--vm"][..]] {
+        let output = tinyc(args, "{ i=1; while (i<100) i=i+i; }");
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert_eq!(stdout(&output), "i: 128\n");
+    }
+}
+
+#[test]
+fn compile() {
+    let output = tinyc(&["compile"], "a=1;");
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "   0  IPUSH 1\n   2  ISTORE 0\n   4  IPOP\n   5  HALT\n"
+    );
+}
+
+#[test]
+fn fmt() {
+    let output = tinyc(&["fmt"], "if(a<b){c=1;}else c=2;");
+    assert!(output.status.success());
+    let formatted = stdout(&output);
+    assert_eq!(formatted, "if (a < b) {\n    c = 1;\n} else\n    c = 2;\n");
+
+    let output = tinyc(&["fmt", "--check"], formatted);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "");
+
+    let output = tinyc(&["fmt", "--check"], "a=1;");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output), "error: <stdin> is not formatted\n");
+
+    let output = tinyc(&["fmt"], "a=;");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("error[E0002]"));
+}
+
+#[test]
+fn diagnostics() {
+    let output = tinyc(&["check"], "a=1;\nb=;");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        "\
+error[E0002]: expected an expression, found `;`
+ --> <stdin>:2:3
+  |
+2 | b=;
+  |   ^ unexpected `;`
+",
+    );
+
+    let output = tinyc(&["run"], "ab=1;");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("error: unknown variable `ab`\n --> <stdin>:1:1\n"));
+    assert!(stdout(&output).is_empty());
+
+    let output = tinyc(&["parse"], "a=1");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!stdout(&output).is_empty());
+}
+
+#[test]
+fn long_chain() {
+    let text = format!("a=a{};", "+a".repeat(32_000));
+    let commands = [
+        &["parse"][..],
+        &["check"],
+        &["run"],
+        &["run", "--vm"],
+        &["compile"],
+        &["fmt"],
+    ];
+    for args in &commands {
+        let output = tinyc(args, &text);
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        assert!(stderr(&output).starts_with("error[E0004]: nesting too deep"));
+    }
+}
+
+#[test]
+fn message_format() {
+    let output = tinyc(&["check", "--message-format", "json"], "a=#;\nb=;");
+    assert_eq!(output.status.code(), Some(1));
+    let diagnostics: Vec<json::Value> = stderr(&output)
+        .lines()
+        .map(|line| json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0]["message"], "unexpected character `#`");
+    assert_eq!(diagnostics[1]["code"], "E0002");
+    assert_eq!(diagnostics[1]["labels"][0]["start"]["line"], 2);
+}
+
+#[test]
+fn color() {
+    let output = tinyc(&["--color", "always", "check"], "a=;");
+    assert!(stderr(&output).starts_with("\x1b[1;31merror[E0002]"));
+    let output = tinyc(&["check", "--color", "never"], "a=;");
+    assert!(stderr(&output).starts_with("error[E0002]"));
+}
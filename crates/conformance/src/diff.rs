@@ -0,0 +1,165 @@
+//! Line-based unified diffs, for showing how actual output differs from expected.
+
+use std::fmt::Write;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// The edits that turn `old` into `new`, one line at a time.
+///
+/// This is a longest common subsequence diff after trimming the common prefix and suffix,
+/// so it is quadratic only in the size of the region that changed.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // lcs[i][j] is the length of the longest common subsequence of a[i..] and b[j..].
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops: Vec<_> = old[..prefix]
+        .iter()
+        .map(|&line| (Op::Equal, line))
+        .collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((Op::Equal, a[i]));
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push((Op::Delete, a[i]));
+            i += 1;
+        } else {
+            ops.push((Op::Insert, b[j]));
+            j += 1;
+        }
+    }
+    ops.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|&line| (Op::Equal, line)),
+    );
+    ops
+}
+
+/// A unified diff from `old` to `new`, with `context` unchanged lines around each change.
+///
+/// The diff is empty if the texts have the same lines.
+pub fn unified(old: &str, new: &str, context: usize) -> String {
+    let old: Vec<_> = old.lines().collect();
+    let new: Vec<_> = new.lines().collect();
+    let ops = diff_lines(&old, &new);
+
+    let changes: Vec<usize> = (0..ops.len()).filter(|&i| ops[i].0 != Op::Equal).collect();
+    let mut out = String::new();
+    let mut changes = changes.into_iter().peekable();
+    while let Some(first) = changes.next() {
+        // Extend the hunk while the next change is close enough to share context.
+        let mut last = first;
+        while let Some(&next) = changes.peek() {
+            if next - last - 1 > 2 * context {
+                break;
+            }
+            last = next;
+            changes.next();
+        }
+        let start = first.saturating_sub(context);
+        let end = usize::min(last + context + 1, ops.len());
+
+        let count = |ops: &[(Op, &str)], op| ops.iter().filter(|(o, _)| *o != op).count();
+        let old_start = count(&ops[..start], Op::Insert);
+        let new_start = count(&ops[..start], Op::Delete);
+        let old_len = count(&ops[start..end], Op::Insert);
+        let new_len = count(&ops[start..end], Op::Delete);
+        let line_number = |start: usize, len: usize| if len == 0 { start } else { start + 1 };
+        writeln!(
+            out,
+            "@@ -{},{} +{},{} @@",
+            line_number(old_start, old_len),
+            old_len,
+            line_number(new_start, new_len),
+            new_len,
+        )
+        .unwrap();
+        for &(op, line) in &ops[start..end] {
+            let sigil = match op {
+                Op::Equal => ' ',
+                Op::Delete => '-',
+                Op::Insert => '+',
+            };
+            writeln!(out, "{}{}", sigil, line).unwrap();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unified;
+
+    #[test]
+    fn hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\nj\nk\n";
+        assert_eq!(
+            unified(old, new, 1),
+            "\
+@@ -1,3 +1,3 @@
+ a
+-b
++B
+ c
+@@ -8,3 +8,3 @@
+ h
+-i
+ j
++k
+",
+        );
+        assert_eq!(
+            unified(old, new, 3),
+            "\
+@@ -1,10 +1,10 @@
+ a
+-b
++B
+ c
+ d
+ e
+ f
+ g
+ h
+-i
+ j
++k
+",
+        );
+    }
+
+    #[test]
+    fn edges() {
+        assert_eq!(unified("a\nb", "a\nb\n", 3), "");
+        assert_eq!(unified("", "a", 3), "@@ -0,0 +1,1 @@\n+a\n");
+        assert_eq!(unified("a\nb", "b", 0), "@@ -1,1 +0,0 @@\n-a\n");
+    }
+}
@@ -0,0 +1,355 @@
+//! Serialization formats for test output.
+//!
+//! A [`Format`] serializes the output of a test function, and normalizes the expected output
+//! written in a test file, so that the two compare equal when they are the same value.
+//!
+//! The stock formats are:
+//!
+//! | name          | type            | output                                       |
+//! |---------------|-----------------|----------------------------------------------|
+//! | `json`        | [`Json`]        | JSON, on one line                            |
+//! | `json_pretty` | [`JsonPretty`]  | JSON, indented                               |
+//! | `yaml`        | [`Yaml`]        | YAML                                         |
+//! | `ron`         | [`Ron`]         | RON, on one line                             |
+//! | `ron_pretty`  | [`RonPretty`]   | RON, indented                                |
+//! | `sexp`        | [`Sexp`]        | S-expressions, on one line                   |
+//! | `sexp_pretty` | [`SexpPretty`]  | S-expressions, as an indented tree           |
+//! | `text`        | [`text`]        | `Display` text, read back with `FromStr`     |
+//!
+//! The [`tests`](crate::tests) attribute takes them by name, as in `format = json_pretty`,
+//! and [`Stock`] picks one of the serde formats by name at runtime.
+//!
+//! The JSON, YAML and RON formats are behind the `json`, `yaml` and `ron` features.
+//!
+//! The S-expression formats are output only: they cannot deserialize,
+//! so they do not work in `superset` mode or with a `value` type.
+
+use {
+    serde::{de::DeserializeOwned, Serialize},
+    std::{error, fmt, str::FromStr},
+};
+
+mod sexp;
+pub mod text;
+
+pub use self::sexp::{Sexp, SexpPretty};
+
+/// A serialization format for test output.
+pub trait Format {
+    type Error: error::Error + 'static;
+
+    /// Serialize a value.
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<String, Self::Error>;
+
+    /// Deserialize a value.
+    fn deserialize<T: DeserializeOwned>(&self, s: &str) -> Result<T, Self::Error>;
+
+    /// Rewrite text in this format as it would be serialized.
+    ///
+    /// This is used when the type of the output is not known or cannot be deserialized,
+    /// so it goes through a value type that can hold anything the format can.
+    fn normalize(&self, s: &str) -> Result<String, Self::Error>;
+}
+
+/// An error from a format that does not have one error type of its own.
+#[derive(Debug)]
+pub struct Error(Box<dyn error::Error + Send + Sync>);
+
+impl Error {
+    fn new(error: impl Into<Box<dyn error::Error + Send + Sync>>) -> Self {
+        Error(error.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+#[cfg(feature = "json")]
+/// JSON, on one line.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Json;
+
+#[cfg(feature = "json")]
+/// JSON, indented.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct JsonPretty;
+
+#[cfg(feature = "json")]
+impl Format for Json {
+    type Error = json::Error;
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<String, json::Error> {
+        json::to_string(value)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, s: &str) -> Result<T, json::Error> {
+        json::from_str(s)
+    }
+
+    fn normalize(&self, s: &str) -> Result<String, json::Error> {
+        self.serialize(&self.deserialize::<json::Value>(s)?)
+    }
+}
+
+#[cfg(feature = "json")]
+impl Format for JsonPretty {
+    type Error = json::Error;
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<String, json::Error> {
+        json::to_string_pretty(value)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, s: &str) -> Result<T, json::Error> {
+        json::from_str(s)
+    }
+
+    fn normalize(&self, s: &str) -> Result<String, json::Error> {
+        self.serialize(&self.deserialize::<json::Value>(s)?)
+    }
+}
+
+#[cfg(feature = "yaml")]
+/// YAML.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Yaml;
+
+#[cfg(feature = "yaml")]
+impl Format for Yaml {
+    type Error = yaml::Error;
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<String, yaml::Error> {
+        let yaml = yaml::to_string(value)?;
+        // A document start marker would read as the separator before the output in a test file.
+        match yaml.strip_prefix("---") {
+            Some(rest) => Ok(rest.trim_start_matches([' ', '\n']).to_string()),
+            None => Ok(yaml),
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, s: &str) -> Result<T, yaml::Error> {
+        yaml::from_str(s)
+    }
+
+    fn normalize(&self, s: &str) -> Result<String, yaml::Error> {
+        self.serialize(&self.deserialize::<yaml::Value>(s)?)
+    }
+}
+
+#[cfg(feature = "ron")]
+/// RON, on one line.
+///
+/// RON's value type cannot hold struct names or enum variants,
+/// so tests of types with those need to give their own `value` type to normalize with.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Ron;
+
+#[cfg(feature = "ron")]
+/// RON, indented.
+///
+/// RON's value type cannot hold struct names or enum variants,
+/// so tests of types with those need to give their own `value` type to normalize with.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct RonPretty;
+
+#[cfg(feature = "ron")]
+impl Format for Ron {
+    type Error = Error;
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<String, Error> {
+        ron::ser::to_string(value).map_err(Error::new)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, s: &str) -> Result<T, Error> {
+        ron::de::from_str(s).map_err(Error::new)
+    }
+
+    fn normalize(&self, s: &str) -> Result<String, Error> {
+        self.serialize(&self.deserialize::<ron::Value>(s)?)
+    }
+}
+
+#[cfg(feature = "ron")]
+impl Format for RonPretty {
+    type Error = Error;
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<String, Error> {
+        ron::ser::to_string_pretty(value, Default::default()).map_err(Error::new)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, s: &str) -> Result<T, Error> {
+        ron::de::from_str(s).map_err(Error::new)
+    }
+
+    fn normalize(&self, s: &str) -> Result<String, Error> {
+        self.serialize(&self.deserialize::<ron::Value>(s)?)
+    }
+}
+
+/// One of the stock formats, picked by name at runtime, as in `--format json_pretty`.
+///
+/// Unlike the [`tests`](crate::tests) attribute, this does not take `text`,
+/// which is not a `Format`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Stock {
+    #[cfg(feature = "json")]
+    Json,
+    #[cfg(feature = "json")]
+    JsonPretty,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    #[cfg(feature = "ron")]
+    Ron,
+    #[cfg(feature = "ron")]
+    RonPretty,
+    Sexp,
+    SexpPretty,
+}
+
+impl Stock {
+    /// The names of the stock formats, as `FromStr` takes them.
+    pub const NAMES: &'static [&'static str] = &[
+        #[cfg(feature = "json")]
+        "json",
+        #[cfg(feature = "json")]
+        "json_pretty",
+        #[cfg(feature = "yaml")]
+        "yaml",
+        #[cfg(feature = "ron")]
+        "ron",
+        #[cfg(feature = "ron")]
+        "ron_pretty",
+        "sexp",
+        "sexp_pretty",
+    ];
+
+    /// The name of this format.
+    pub fn name(self) -> &'static str {
+        match self {
+            #[cfg(feature = "json")]
+            Stock::Json => "json",
+            #[cfg(feature = "json")]
+            Stock::JsonPretty => "json_pretty",
+            #[cfg(feature = "yaml")]
+            Stock::Yaml => "yaml",
+            #[cfg(feature = "ron")]
+            Stock::Ron => "ron",
+            #[cfg(feature = "ron")]
+            Stock::RonPretty => "ron_pretty",
+            Stock::Sexp => "sexp",
+            Stock::SexpPretty => "sexp_pretty",
+        }
+    }
+}
+
+impl fmt::Display for Stock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Stock {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            #[cfg(feature = "json")]
+            "json" => Ok(Stock::Json),
+            #[cfg(feature = "json")]
+            "json_pretty" => Ok(Stock::JsonPretty),
+            #[cfg(feature = "yaml")]
+            "yaml" => Ok(Stock::Yaml),
+            #[cfg(feature = "ron")]
+            "ron" => Ok(Stock::Ron),
+            #[cfg(feature = "ron")]
+            "ron_pretty" => Ok(Stock::RonPretty),
+            "sexp" => Ok(Stock::Sexp),
+            "sexp_pretty" => Ok(Stock::SexpPretty),
+            _ => Err(Error::new(format!("unknown format `{}`", s))),
+        }
+    }
+}
+
+impl Format for Stock {
+    type Error = Error;
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<String, Error> {
+        match self {
+            #[cfg(feature = "json")]
+            Stock::Json => Json.serialize(value).map_err(Error::new),
+            #[cfg(feature = "json")]
+            Stock::JsonPretty => JsonPretty.serialize(value).map_err(Error::new),
+            #[cfg(feature = "yaml")]
+            Stock::Yaml => Yaml.serialize(value).map_err(Error::new),
+            #[cfg(feature = "ron")]
+            Stock::Ron => Ron.serialize(value),
+            #[cfg(feature = "ron")]
+            Stock::RonPretty => RonPretty.serialize(value),
+            Stock::Sexp => Sexp.serialize(value),
+            Stock::SexpPretty => SexpPretty.serialize(value),
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, s: &str) -> Result<T, Error> {
+        match self {
+            #[cfg(feature = "json")]
+            Stock::Json => Json.deserialize(s).map_err(Error::new),
+            #[cfg(feature = "json")]
+            Stock::JsonPretty => JsonPretty.deserialize(s).map_err(Error::new),
+            #[cfg(feature = "yaml")]
+            Stock::Yaml => Yaml.deserialize(s).map_err(Error::new),
+            #[cfg(feature = "ron")]
+            Stock::Ron => Ron.deserialize(s),
+            #[cfg(feature = "ron")]
+            Stock::RonPretty => RonPretty.deserialize(s),
+            Stock::Sexp => Sexp.deserialize(s),
+            Stock::SexpPretty => SexpPretty.deserialize(s),
+        }
+    }
+
+    fn normalize(&self, s: &str) -> Result<String, Error> {
+        match self {
+            #[cfg(feature = "json")]
+            Stock::Json => Json.normalize(s).map_err(Error::new),
+            #[cfg(feature = "json")]
+            Stock::JsonPretty => JsonPretty.normalize(s).map_err(Error::new),
+            #[cfg(feature = "yaml")]
+            Stock::Yaml => Yaml.normalize(s).map_err(Error::new),
+            #[cfg(feature = "ron")]
+            Stock::Ron => Ron.normalize(s),
+            #[cfg(feature = "ron")]
+            Stock::RonPretty => RonPretty.normalize(s),
+            Stock::Sexp => Sexp.normalize(s),
+            Stock::SexpPretty => SexpPretty.normalize(s),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "yaml"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stock_names() {
+        for &name in Stock::NAMES {
+            assert_eq!(name.parse::<Stock>().unwrap().name(), name);
+        }
+        assert!("text".parse::<Stock>().is_err());
+        assert_eq!(Stock::Yaml.serialize(&[1, 2]).unwrap(), "- 1\n- 2");
+    }
+
+    #[test]
+    fn yaml_without_document_marker() {
+        assert_eq!(Yaml.serialize(&1).unwrap(), "1");
+        assert_eq!(Yaml.serialize(&[1, 2]).unwrap(), "- 1\n- 2");
+        assert_eq!(Yaml.normalize("---\n- 1\n- 2").unwrap(), "- 1\n- 2");
+    }
+}
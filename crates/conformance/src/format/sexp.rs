@@ -0,0 +1,647 @@
+//! S-expressions, as a human-readable dump of the tree of a value.
+//!
+//! - booleans and numbers are bare symbols, and strings and characters are quoted;
+//! - units, `None` and empty sequences are `()`;
+//! - sequences and tuples are lists: `(1 2 3)`;
+//! - unit structs and variants are their name: `Semicolon`;
+//! - other structs and variants are lists that start with their name:
+//!   `(Identifier 1)`, `(Point (x 1) (y 2))`;
+//! - maps are lists of entries, and a map with one entry is just that entry;
+//! - entries and fields are lists of their key and value: `(x 1)`,
+//!   except that the elements of a sequence value are spliced in: `(Program (..) (..))`.
+//!
+//! Text in this format can contain `;` line comments.
+//!
+//! This encoding is for reading, not for reading back: a spliced sequence or a map with one entry
+//! looks the same as a struct, and `()` is any of several values.
+//! So the format is output only, and `deserialize` always fails;
+//! expected output is compared after `normalize`, which only reformats the S-expression.
+
+use {
+    super::{Error, Format},
+    serde::{
+        de::DeserializeOwned,
+        ser::{self, Serialize},
+    },
+    std::{fmt, fmt::Write, iter::Peekable, str::CharIndices},
+};
+
+/// S-expressions, on one line.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Sexp;
+
+/// S-expressions, as an indented tree.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SexpPretty;
+
+impl Format for Sexp {
+    type Error = Error;
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<String, Error> {
+        Ok(value.serialize(Serializer)?.to_string())
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, _s: &str) -> Result<T, Error> {
+        Err(output_only())
+    }
+
+    fn normalize(&self, s: &str) -> Result<String, Error> {
+        Ok(parse(s)?.to_string())
+    }
+}
+
+impl Format for SexpPretty {
+    type Error = Error;
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<String, Error> {
+        Ok(format!("{:#}", value.serialize(Serializer)?))
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, _s: &str) -> Result<T, Error> {
+        Err(output_only())
+    }
+
+    fn normalize(&self, s: &str) -> Result<String, Error> {
+        Ok(format!("{:#}", parse(s)?))
+    }
+}
+
+fn output_only() -> Error {
+    Error::new("S-expressions are an output-only format and cannot be deserialized")
+}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::new(msg.to_string())
+    }
+}
+
+/// An S-expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Symbol(String),
+    String(String),
+    List(Vec<Node>),
+}
+
+fn symbol(s: impl ToString) -> Node {
+    Node::Symbol(s.to_string())
+}
+
+const INDENT: &str = "  ";
+
+impl Node {
+    /// Is this a list that is not empty?
+    fn is_nested(&self) -> bool {
+        matches!(self, Node::List(nodes) if !nodes.is_empty())
+    }
+
+    /// Write a list with nested lists in it with each of its elements on their own line.
+    fn write_pretty(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let nodes = match self {
+            Node::List(nodes) if nodes.iter().any(Node::is_nested) => nodes,
+            _ => return write!(f, "{}", self),
+        };
+        // Elements before the first nested list, like a name, stay on the first line.
+        let head = nodes.iter().take_while(|node| !node.is_nested()).count();
+        f.write_char('(')?;
+        for (i, node) in nodes.iter().enumerate() {
+            if i < head {
+                if i > 0 {
+                    f.write_char(' ')?;
+                }
+                write!(f, "{}", node)?;
+            } else {
+                if i > 0 {
+                    f.write_char('\n')?;
+                    for _ in 0..=depth {
+                        f.write_str(INDENT)?;
+                    }
+                }
+                node.write_pretty(f, depth + 1)?;
+            }
+        }
+        f.write_char(')')
+    }
+}
+
+/// Compact by default, and indented with `{:#}`.
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            return self.write_pretty(f, 0);
+        }
+        match self {
+            Node::Symbol(symbol) => f.write_str(symbol),
+            Node::String(string) => {
+                f.write_char('"')?;
+                for c in string.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c => f.write_char(c)?,
+                    }
+                }
+                f.write_char('"')
+            }
+            Node::List(nodes) => {
+                f.write_char('(')?;
+                for (i, node) in nodes.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(' ')?;
+                    }
+                    write!(f, "{}", node)?;
+                }
+                f.write_char(')')
+            }
+        }
+    }
+}
+
+/// Parse the one S-expression in `s`.
+fn parse(s: &str) -> Result<Node, Error> {
+    let mut parser = Parser {
+        source: s,
+        chars: s.char_indices().peekable(),
+    };
+    let node = parser.node()?;
+    match parser.peek() {
+        None => Ok(node),
+        Some((ix, _)) => Err(Error::new(format!("unexpected text at byte {}", ix))),
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    /// The next character that is not whitespace or in a comment.
+    fn peek(&mut self) -> Option<(usize, char)> {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if c == ';' {
+                while self.chars.next_if(|&(_, c)| c != '\n').is_some() {}
+            } else if c.is_whitespace() {
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        self.chars.peek().copied()
+    }
+
+    fn node(&mut self) -> Result<Node, Error> {
+        match self.peek() {
+            None => Err(Error::new(
+                "expected an S-expression, found the end of the text",
+            )),
+            Some((ix, ')')) => Err(Error::new(format!("unmatched `)` at byte {}", ix))),
+            Some((_, '(')) => {
+                self.chars.next();
+                let mut nodes = vec![];
+                loop {
+                    match self.peek() {
+                        Some((_, ')')) => break,
+                        None => return Err(Error::new("unclosed `(`")),
+                        Some(_) => nodes.push(self.node()?),
+                    }
+                }
+                self.chars.next();
+                Ok(Node::List(nodes))
+            }
+            Some((_, '"')) => {
+                self.chars.next();
+                let mut string = String::new();
+                loop {
+                    match self.chars.next().map(|(_, c)| c) {
+                        Some('"') => break,
+                        Some('\\') => match self.chars.next().map(|(_, c)| c) {
+                            Some('n') => string.push('\n'),
+                            Some('r') => string.push('\r'),
+                            Some('t') => string.push('\t'),
+                            Some(c @ '"') | Some(c @ '\\') => string.push(c),
+                            _ => return Err(Error::new("invalid escape in string")),
+                        },
+                        Some(c) => string.push(c),
+                        None => return Err(Error::new("unclosed string")),
+                    }
+                }
+                Ok(Node::String(string))
+            }
+            Some((start, _)) => {
+                let mut end = self.source.len();
+                while let Some(&(ix, c)) = self.chars.peek() {
+                    if c.is_whitespace() || "();\"".contains(c) {
+                        end = ix;
+                        break;
+                    }
+                    self.chars.next();
+                }
+                Ok(symbol(&self.source[start..end]))
+            }
+        }
+    }
+}
+
+struct Serializer;
+
+/// An entry or field, with the elements of a sequence value spliced in.
+fn entry(key: Node, value: Node) -> Node {
+    // Keys that read as names are written as symbols.
+    let key = match key {
+        Node::String(key) if is_name(&key) => Node::Symbol(key),
+        key => key,
+    };
+    match value {
+        Node::List(mut nodes) if !nodes.is_empty() && is_seq(&nodes) => {
+            nodes.insert(0, key);
+            Node::List(nodes)
+        }
+        value => Node::List(vec![key, value]),
+    }
+}
+
+fn is_name(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && !s.contains(|c: char| c.is_whitespace() || "();\"".contains(c))
+        && s != "true"
+        && s != "false"
+}
+
+/// Can these be the elements of a sequence value spliced into an entry?
+/// They cannot if the value would read as a named struct or variant instead.
+fn is_seq(nodes: &[Node]) -> bool {
+    !matches!(nodes.first(), Some(Node::Symbol(_))) || nodes.len() == 1
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = Node;
+    type Error = Error;
+    type SerializeSeq = List;
+    type SerializeTuple = List;
+    type SerializeTupleStruct = List;
+    type SerializeTupleVariant = List;
+    type SerializeMap = Map;
+    type SerializeStruct = List;
+    type SerializeStructVariant = List;
+
+    fn serialize_bool(self, v: bool) -> Result<Node, Error> {
+        Ok(symbol(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Node, Error> {
+        Ok(symbol(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Node, Error> {
+        Ok(symbol(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Node, Error> {
+        Ok(symbol(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Node, Error> {
+        Ok(symbol(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Node, Error> {
+        Ok(symbol(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Node, Error> {
+        Ok(symbol(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Node, Error> {
+        Ok(symbol(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Node, Error> {
+        Ok(symbol(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Node, Error> {
+        Ok(symbol(format!("{:?}", v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Node, Error> {
+        Ok(symbol(format!("{:?}", v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Node, Error> {
+        Ok(Node::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Node, Error> {
+        Ok(Node::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Node, Error> {
+        Ok(Node::List(v.iter().map(symbol).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Node, Error> {
+        Ok(Node::List(vec![]))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Node, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Node, Error> {
+        Ok(Node::List(vec![]))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Node, Error> {
+        Ok(symbol(name))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Node, Error> {
+        Ok(symbol(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Node, Error> {
+        Ok(Node::List(vec![symbol(name), value.serialize(self)?]))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Node, Error> {
+        Ok(Node::List(vec![symbol(variant), value.serialize(self)?]))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<List, Error> {
+        Ok(List(vec![]))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<List, Error> {
+        Ok(List(vec![]))
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, _len: usize) -> Result<List, Error> {
+        Ok(List(vec![symbol(name)]))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<List, Error> {
+        Ok(List(vec![symbol(variant)]))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Map, Error> {
+        Ok(Map {
+            entries: vec![],
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<List, Error> {
+        Ok(List(vec![symbol(name)]))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<List, Error> {
+        Ok(List(vec![symbol(variant)]))
+    }
+}
+
+/// The elements of a list being serialized.
+struct List(Vec<Node>);
+
+impl List {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.0.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn push_field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), Error> {
+        self.0
+            .push(entry(symbol(key), value.serialize(Serializer)?));
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for List {
+    type Ok = Node;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Node, Error> {
+        Ok(Node::List(self.0))
+    }
+}
+
+impl ser::SerializeTuple for List {
+    type Ok = Node;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Node, Error> {
+        Ok(Node::List(self.0))
+    }
+}
+
+impl ser::SerializeTupleStruct for List {
+    type Ok = Node;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Node, Error> {
+        Ok(Node::List(self.0))
+    }
+}
+
+impl ser::SerializeTupleVariant for List {
+    type Ok = Node;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Node, Error> {
+        Ok(Node::List(self.0))
+    }
+}
+
+impl ser::SerializeStruct for List {
+    type Ok = Node;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.push_field(key, value)
+    }
+
+    fn end(self) -> Result<Node, Error> {
+        Ok(Node::List(self.0))
+    }
+}
+
+impl ser::SerializeStructVariant for List {
+    type Ok = Node;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.push_field(key, value)
+    }
+
+    fn end(self) -> Result<Node, Error> {
+        Ok(Node::List(self.0))
+    }
+}
+
+/// The entries of a map being serialized.
+struct Map {
+    entries: Vec<Node>,
+    key: Option<Node>,
+}
+
+impl ser::SerializeMap for Map {
+    type Ok = Node;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(key.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        self.entries.push(entry(key, value.serialize(Serializer)?));
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<Node, Error> {
+        if self.entries.len() == 1 {
+            Ok(self.entries.pop().unwrap())
+        } else {
+            Ok(Node::List(self.entries))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde::Serialize, std::collections::BTreeMap};
+
+    #[derive(Serialize)]
+    enum Token {
+        Identifier(u32),
+        Semicolon,
+        Range { start: u32, end: u32 },
+    }
+
+    #[derive(Serialize)]
+    struct Tokens {
+        name: String,
+        tokens: Vec<Token>,
+        ok: Option<bool>,
+        none: Vec<u8>,
+    }
+
+    #[test]
+    fn serialize() {
+        let tokens = Tokens {
+            name: "a \"b\"\n".into(),
+            tokens: vec![
+                Token::Identifier(1),
+                Token::Semicolon,
+                Token::Range { start: 1, end: 2 },
+            ],
+            ok: Some(true),
+            none: vec![],
+        };
+        let compact = Sexp.serialize(&tokens).unwrap();
+        assert_eq!(
+            compact,
+            r#"(Tokens (name "a \"b\"\n") (tokens (Identifier 1) Semicolon (Range (start 1) (end 2))) (ok true) (none ()))"#,
+        );
+        let pretty = SexpPretty.serialize(&tokens).unwrap();
+        assert_eq!(
+            pretty,
+            r#"(Tokens
+  (name "a \"b\"\n")
+  (tokens
+    (Identifier 1)
+    Semicolon
+    (Range
+      (start 1)
+      (end 2)))
+  (ok true)
+  (none ()))"#,
+        );
+        assert_eq!(
+            Sexp.normalize(&format!("; comment\n{}", pretty)).unwrap(),
+            compact
+        );
+    }
+
+    #[test]
+    fn maps() {
+        let mut tree = BTreeMap::new();
+        tree.insert("Program", vec![BTreeMap::from([("Semicolon", ";")])]);
+        let text = Sexp.serialize(&tree).unwrap();
+        assert_eq!(text, r#"(Program (Semicolon ";"))"#);
+        assert!(Sexp.normalize("(a").is_err());
+        assert!(Sexp.normalize("a)").is_err());
+    }
+
+    #[test]
+    fn output_only() {
+        // these would all read back as something else
+        let text = Sexp.serialize(&Some(())).unwrap();
+        assert_eq!(text, "()");
+        assert!(Sexp.deserialize::<Option<()>>(&text).is_err());
+        let text = Sexp.serialize(&vec![vec![1], vec![], vec![2]]).unwrap();
+        assert_eq!(text, "((1) () (2))");
+        assert!(Sexp.deserialize::<Vec<Vec<i32>>>(&text).is_err());
+        assert!(SexpPretty.deserialize::<i32>("1").is_err());
+    }
+}
@@ -0,0 +1,18 @@
+//! Plain text, written with `Display` and read with `FromStr`.
+//!
+//! This is not a [`Format`](super::Format), which works with any `Serialize` type,
+//! but a pair of functions for types that have a text form of their own.
+//! The [`tests`](crate::tests) attribute takes it as `format = text`,
+//! and normalizes the expected output by reading it as the tested type and writing it back.
+
+use std::{fmt, str::FromStr};
+
+/// Write a value as its `Display` text.
+pub fn to_string<T: fmt::Display + ?Sized>(value: &T) -> Result<String, fmt::Error> {
+    Ok(value.to_string())
+}
+
+/// Read a value from its text with `FromStr`.
+pub fn from_str<T: FromStr>(s: &str) -> Result<T, T::Err> {
+    s.parse()
+}
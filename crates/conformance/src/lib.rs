@@ -1,308 +1,173 @@
-extern crate proc_macro;
-
-use {
-    proc_macro2::{Span, TokenStream},
-    quote::{quote, quote_spanned},
-    std::{
-        env,
-        fs::File,
-        io::prelude::*,
-        path::{Path, PathBuf},
-    },
-    syn::parse::Parse,
+//! Conformance testing for `fn(&str) -> impl Serialize`.
+//!
+//! See the [`tests`] attribute for how to write conformance tests,
+//! or the [`runner`] module to find and run test files at runtime instead.
+//! The rest of this crate is support code used by the tests they generate.
+//!
+//! The stock formats that use a serde crate, `json`, `yaml` and `ron`,
+//! and the `runner`, are each behind a Cargo feature of that name.
+//! All of them are enabled by default.
+
+#[doc(hidden)]
+pub use conformance_file::Outcome;
+pub use conformance_macros::tests;
+pub use format::Format;
+
+use std::{
+    error::Error,
+    fs, io, process,
+    sync::{Mutex, PoisonError},
 };
 
-fn compile_error(s: &str, span: Span) -> TokenStream {
-    quote_spanned!(span=> compile_error! { #s })
-}
-
-struct AttrArgs {
-    ser: syn::ExprPath,
-    de: syn::ExprPath,
-    value: Option<syn::Type>,
-    file: syn::LitStr,
-}
-
-impl Parse for AttrArgs {
-    fn parse(input: &syn::parse::ParseBuffer<'_>) -> syn::parse::Result<Self> {
-        mod kw {
-            syn::custom_keyword!(exact);
-            syn::custom_keyword!(file);
-            syn::custom_keyword!(ser);
-            syn::custom_keyword!(de);
-            syn::custom_keyword!(value);
-            syn::custom_keyword!(serde);
-        }
-
-        // TODO: add `superset` mode where actual is "at least" expected
-        let _: kw::exact = input.parse()?;
-        let _: syn::Token![,] = input.parse()?;
-
-        let la = input.lookahead1();
-        let (ser, de, value) = if la.peek(kw::serde) {
-            let _: kw::serde = input.parse()?;
-            let _: syn::Token![=] = input.parse()?;
-            let format: syn::ExprPath = input.parse()?;
-            let _: syn::Token![,] = input.parse()?;
-
-            // FUTURE(rust-lang/rust#64797): use #[cfg(accessible)] to prefer `to_string_pretty`
-            // as well as fall back to the `::ser::to_string`/`::de::from_str`/`::value::Value`
-
-            // for errors
-            let la = input.lookahead1();
-            if !(la.peek(kw::ser) || la.peek(kw::de) || la.peek(kw::value) | la.peek(kw::file)) {
-                return Err(la.error());
-            }
-
-            let ser: syn::ExprPath = if input.peek(kw::ser) {
-                let _: kw::ser = input.parse()?;
-                let _: syn::Token![=] = input.parse()?;
-                let ser: syn::ExprPath = input.parse()?;
-                let _: syn::Token![,] = input.parse()?;
-
-                // for errors
-                let la = input.lookahead1();
-                if !(la.peek(kw::de) || la.peek(kw::value) | la.peek(kw::file)) {
-                    return Err(la.error());
-                }
-
-                ser
-            } else {
-                syn::parse_quote!(#format::to_string)
-            };
-
-            let de: syn::ExprPath = if input.peek(kw::de) {
-                let _: kw::de = input.parse()?;
-                let _: syn::Token![=] = input.parse()?;
-                let de: syn::ExprPath = input.parse()?;
-                let _: syn::Token![,] = input.parse()?;
-
-                // for errors
-                let la = input.lookahead1();
-                if !(la.peek(kw::value) | la.peek(kw::file)) {
-                    return Err(la.error());
-                }
-
-                de
-            } else {
-                syn::parse_quote!(#format::from_str)
-            };
-
-            let value: syn::Type = if input.peek(kw::value) {
-                let _: kw::value = input.parse()?;
-                let _: syn::Token![=] = input.parse()?;
-                let value: syn::Type = input.parse()?;
-                let _: syn::Token![,] = input.parse()?;
-                value
-            } else {
-                syn::parse_quote!(#format::Value)
-            };
-
-            (ser, de, Some(value))
-        } else if la.peek(kw::ser) {
-            let _: kw::ser = input.parse()?;
-            let _: syn::Token![=] = input.parse()?;
-            let ser: syn::ExprPath = input.parse()?;
-            let _: syn::Token![,] = input.parse()?;
-
-            let _: kw::de = input.parse()?;
-            let _: syn::Token![=] = input.parse()?;
-            let de: syn::ExprPath = input.parse()?;
-            let _: syn::Token![,] = input.parse()?;
-
-            let value = if input.peek(kw::value) {
-                let _: kw::value = input.parse()?;
-                let _: syn::Token![=] = input.parse()?;
-                let value: syn::Type = input.parse()?;
-                let _: syn::Token![,] = input.parse()?;
-                Some(value)
-            } else {
-                None
-            };
-
-            (ser, de, value)
-        } else {
-            return Err(la.error());
-        };
-
-        let _: kw::file = input.parse()?;
-        let _: syn::Token![=] = input.parse()?;
-        let file: syn::LitStr = input.parse()?;
-
-        Ok(AttrArgs {
-            ser,
-            de,
-            value,
-            file,
-        })
-    }
-}
-
-struct Test {
-    name: syn::Ident,
-    input: String,
-    output: String,
-}
-
-fn read_tests(file_path: &Path, span: Span) -> Result<Vec<Test>, TokenStream> {
-    let source = {
-        let mut f = File::open(file_path)
-            .map_err(|e| compile_error(&format!("failed to open file: {}", e), span))?;
-        let mut s = String::with_capacity(f.metadata().map(|m| m.len() as usize + 1).unwrap_or(0));
-        f.read_to_string(&mut s)
-            .map_err(|e| compile_error(&format!("failed to read file: {}", e), span))?;
-        s
+pub mod diff;
+pub mod format;
+#[cfg(feature = "runner")]
+pub mod runner;
+pub mod superset;
+
+/// The environment variable that, when set, makes tests rewrite their expected output.
+pub const BLESS_VAR: &str = "CONFORMANCE_BLESS";
+
+/// Held while blessing, as the tests in a binary run in parallel
+/// and may rewrite the same file.
+static BLESS_LOCK: Mutex<()> = Mutex::new(());
+
+/// Lines of unchanged context around each change in a failure's diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// Panic if the function did not return the `expected` variant of its result.
+///
+/// `path` and `line` say where the failing test case starts.
+#[track_caller]
+pub fn assert_outcome(path: &str, line: usize, expected: Outcome, actual: Outcome, output: &str) {
+    let returned = |outcome| match outcome {
+        Outcome::Ok => "`Ok`",
+        Outcome::Err | Outcome::ErrMessage => "`Err`",
     };
-
-    if !source.ends_with('\n') {
-        return Err(compile_error("file needs to have trailing newline", span));
-    }
-
-    let (s, trailing) = source.split_at(source.rfind("\n...\n").map_or(0, |i| i + 5));
-    if !trailing.trim().is_empty() {
-        return Err(compile_error(
-            "file has disallowed content after final `...`",
-            span,
-        ));
-    }
-
-    let mut tests = Vec::new();
-    let mut errs = TokenStream::new();
-
-    for (i, test) in s.split_terminator("\n...\n").enumerate() {
-        let i: usize = i;
-        let test: &str = test;
-
-        let (name, rest) = match test.find("\n===\n") {
-            Some(ix) => (&test[0..ix], &test[ix + 5..]),
-            None => {
-                errs.extend(compile_error(
-                    &format!("test {} does not have `===` after name", i),
-                    span,
-                ));
-                continue;
-            }
-        };
-        let name = name.trim().replace(' ', "_");
-
-        let (input, output) = match rest.rfind("\n---\n") {
-            Some(ix) => (&rest[0..ix], &rest[ix + 5..]),
-            None => {
-                errs.extend(compile_error(
-                    &format!("test `{}` does not have `---` after input", name),
-                    span,
-                ));
-                continue;
-            }
-        };
-        let input = input.trim().to_string();
-        let output = output.trim().to_string();
-
-        let name = match syn::parse_str::<syn::Ident>(&format!("_{}", name)) {
-            Ok(name) => name,
-            Err(_) => {
-                errs.extend(compile_error(
-                    &format!("`{}` is not a valid test name identifier", name),
-                    span,
-                ));
-                continue;
-            }
-        };
-
-        tests.push(Test {
-            name,
-            input,
+    if returned(expected) != returned(actual) {
+        panic!(
+            "expected {}, but the function returned {}, for the test case at {}:{}\n{}\n{}",
+            returned(expected),
+            returned(actual),
+            path,
+            line,
+            actual.separator(),
             output,
-        })
+        );
     }
+}
 
-    if errs.is_empty() {
-        Ok(tests)
-    } else {
-        Err(errs)
+/// Panic with a diff if the `actual` output is not the `expected` output.
+///
+/// `path` and `line` say where the failing test case starts.
+#[track_caller]
+pub fn assert_output(path: &str, line: usize, expected: &str, actual: &str) {
+    if let Some(message) = mismatch(path, line, expected, actual) {
+        panic!("{}", message);
     }
 }
 
-#[proc_macro_attribute]
-pub fn tests(
-    attr: proc_macro::TokenStream,
-    item: proc_macro::TokenStream,
-) -> proc_macro::TokenStream {
-    // we want to re-emit the notated item in all cases
-    let mut tts: TokenStream = item.clone().into();
-
-    // emit as many environment compile errors as possible in one place
-    let manifest_dir = env::var("CARGO_MANIFEST_DIR")
-        .map(PathBuf::from)
-        .map_err(|e| {
-            let e = format!("expected $CARGO_MANIFEST_DIR; {}", e);
-            compile_error(&e, Span::call_site())
-        });
-    let args = syn::parse::<AttrArgs>(attr).map_err(|e| e.to_compile_error());
-    let fun = syn::parse::<syn::ItemFn>(item).map_err(|e| e.to_compile_error());
-
-    match (args, fun, manifest_dir) {
-        (Ok(args), Ok(fun), Ok(manifest_dir)) => tts.extend(build_tests(args, fun, manifest_dir)),
-        (Err(a), Err(b), Err(c)) => tts.extend(vec![a, b, c]),
-        (Err(a), Err(b), _) | (Err(a), _, Err(b)) | (_, Err(a), Err(b)) => tts.extend(vec![a, b]),
-        (Err(a), _, _) | (_, Err(a), _) | (_, _, Err(a)) => tts.extend(vec![a]),
+/// The failure message if the `actual` output is not the `expected` output.
+fn mismatch(path: &str, line: usize, expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
     }
-
-    tts.into()
+    let mut diff = diff::unified(expected, actual, DIFF_CONTEXT);
+    if diff.is_empty() {
+        diff = format!("-{:?}\n+{:?}\n", expected, actual);
+    }
+    Some(format!(
+        "output does not match, for the test case at {}:{}\n--- expected\n+++ actual\n{}",
+        path, line, diff,
+    ))
 }
 
-fn build_tests(args: AttrArgs, fun: syn::ItemFn, manifest_dir: PathBuf) -> TokenStream {
-    let AttrArgs {
-        ser,
-        de,
-        value,
-        file,
-    } = args;
-    let fn_name = &fun.sig.ident;
-    let tested_type = match &fun.sig.output {
-        syn::ReturnType::Type(_, r#type) => (**r#type).clone(),
-        syn::ReturnType::Default => syn::parse_str("()").unwrap(),
+/// Panic if the `actual` output does not contain the partial `expected` output,
+/// after deserializing both with `de`.
+///
+/// The failure shows the innermost part of the expected output that is not contained,
+/// and the actual output in its place, both serialized with `ser`.
+/// `path` and `line` say where the failing test case starts.
+#[track_caller]
+pub fn assert_contains<E, F>(
+    path: &str,
+    line: usize,
+    expected: &str,
+    actual: &str,
+    de: impl Fn(&str) -> Result<superset::Value, E>,
+    ser: impl Fn(&superset::Value) -> Result<String, F>,
+) -> Result<(), Box<dyn Error>>
+where
+    E: Into<Box<dyn Error>>,
+    F: Into<Box<dyn Error>>,
+{
+    let expected = de(expected).map_err(Into::into)?;
+    let actual = de(actual).map_err(Into::into)?;
+    let mismatch = match actual.mismatch(&expected) {
+        Some(mismatch) => mismatch,
+        None => return Ok(()),
     };
-    let de_type = value.unwrap_or(tested_type);
-
-    let tests_path = manifest_dir.join(file.value());
-    let tests = match read_tests(&tests_path, file.span()) {
-        Ok(it) => it,
-        Err(e) => return e,
+    let at = match &*mismatch.path {
+        "" => String::new(),
+        path => format!(" at `{}`", path),
     };
-
-    let filepath = tests_path.to_string_lossy().to_string();
-    let filename = tests_path
-        .file_stem()
-        .unwrap()
-        .to_string_lossy()
-        .replace('.', "_");
-    let testing_fn = syn::Ident::new(&filename, Span::call_site());
-
-    let mut tts = quote! {
-        fn #testing_fn(expected: &str, actual: &str) -> Result<(), Box<dyn ::std::error::Error>> {
-            const _: &str = include_str!(#filepath);
-            let actual = #ser(&#fn_name(actual))?;
-            let expected = #ser(&#de::<#de_type>(expected)?)?; // normalize
-            assert_eq!(actual, expected);
-            Ok(())
+    let expected = ser(mismatch.expected).map_err(Into::into)?;
+    let details = match mismatch.actual {
+        Some(actual) => {
+            let actual = ser(actual).map_err(Into::into)?;
+            let diff = diff::unified(expected.trim(), actual.trim(), DIFF_CONTEXT);
+            format!("--- expected (partial)\n+++ actual\n{}", diff)
         }
+        None => format!("which is missing, expected (partial):\n{}", expected.trim()),
     };
+    panic!(
+        "output does not contain the expected output{}, for the test case at {}:{}\n{}",
+        at, path, line, details,
+    );
+}
 
-    for test in tests {
-        let Test {
-            name,
-            input,
-            output,
-        } = test;
-        let test_name = quote::format_ident!("{}{}", filename, name);
-        tts.extend(quote! {
-            #[test]
-            fn #test_name() -> Result<(), Box<dyn ::std::error::Error>> {
-                #testing_fn(#output, #input)
-            }
-        })
+/// Replace the expected output in the `section` of the named test in the test file at `path`,
+/// and the separator line before it with that of the `outcome`.
+///
+/// Only one test rewrites a file at a time, and the new contents are written to a temporary file
+/// that then replaces it, so that the file is never left half written.
+#[doc(hidden)]
+pub fn bless(
+    path: &str,
+    name: &str,
+    section: Option<&str>,
+    outcome: Outcome,
+    output: &str,
+) -> io::Result<()> {
+    let output = output.trim();
+    let _guard = BLESS_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut source = fs::read_to_string(path)?;
+
+    let tests = conformance_file::parse(&source).map_err(|errors| {
+        let message = format!("{}:{}: {}", path, errors[0].line, errors[0].message);
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    })?;
+
+    let test = tests.iter().find(|test| test.name == name);
+    if let Some(test_section) = test.and_then(|test| test.section(section)) {
+        let separator = conformance_file::separator(section, outcome);
+        let replacement = match output {
+            "" => format!("{}\n", separator),
+            _ => format!("{}\n{}\n", separator, output),
+        };
+        source.replace_range(test_section.range.clone(), &replacement);
+        let temp = format!("{}.{}.bless", path, process::id());
+        fs::write(&temp, source)?;
+        return fs::rename(&temp, path);
     }
-
-    tts
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        match section {
+            Some(section) => format!(
+                "test `{}` section `{}` not found in {}",
+                name, section, path
+            ),
+            None => format!("test `{}` not found in {}", name, path),
+        },
+    ))
 }
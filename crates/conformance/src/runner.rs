@@ -0,0 +1,267 @@
+//! Finding and running conformance test files at runtime.
+//!
+//! Where the [`tests`](crate::tests) attribute reads one test file at compile time,
+//! a [`Runner`] walks a directory when the tests are run, and tests every `**/*.test` file in it.
+//! New test files are picked up without changing or recompiling any Rust code.
+//!
+//! A runner replaces the standard test harness, so it goes in a test target with `harness = false`:
+//!
+//! ```toml
+//! [[test]]
+//! name = "programs"
+//! harness = false
+//! ```
+//!
+//! ```rust,ignore
+//! fn main() {
+//!     conformance::runner::Runner::new()
+//!         .exact("tests/programs", conformance::format::Yaml, lex_tokens)
+//!         .run()
+//! }
+//! ```
+//!
+//! Its output and command line arguments are those of the standard test harness,
+//! so `cargo test` and test filters work as usual.
+//! Each test is named after the path of its file relative to the directory, without `.test`,
+//! and the name of the test case, like `loops/while.yaml::countdown`.
+//!
+//! Tests are compared as in `exact` mode, and are blessed when `CONFORMANCE_BLESS` is set.
+//! Directives before the name of a test case work as they do for the attribute,
+//! except that `@tag`s are shown in brackets before the test name instead of added to it,
+//! and that ignored tests do not show the reason they were ignored.
+//! Tests that expect an `Err` fail, as the runner does not support `Result` outputs,
+//! as do tests with named sections, as the runner has only the one function to test.
+
+use {
+    crate::Format,
+    conformance_file::Outcome,
+    libtest_mimic::{Arguments, Failed, Trial},
+    serde::Serialize,
+    std::{
+        any::Any,
+        env, fs,
+        panic::{self, AssertUnwindSafe},
+        path::{Path, PathBuf},
+        sync::Arc,
+    },
+    walkdir::WalkDir,
+};
+
+/// A collection of conformance tests, found in test files at runtime.
+#[derive(Default)]
+pub struct Runner {
+    trials: Vec<Trial>,
+}
+
+impl Runner {
+    /// A runner without any tests.
+    pub fn new() -> Self {
+        Runner::default()
+    }
+
+    /// Add a test for every test case in the `**/*.test` files in `dir`.
+    ///
+    /// Each input is passed to `test`, and its result serialized in the `format`.
+    /// The expected output is normalized by the format.
+    /// A file that cannot be read or has malformed test cases adds a failing test instead,
+    /// as does a directory that cannot be read, or a `dir` without any test files.
+    pub fn exact<F, T>(
+        &mut self,
+        dir: impl AsRef<Path>,
+        format: F,
+        test: fn(&str) -> T,
+    ) -> &mut Self
+    where
+        F: Format + Clone + Send + 'static,
+        T: Serialize + 'static,
+    {
+        let dir = dir.as_ref();
+        let files = test_files(dir);
+        if files.is_empty() {
+            let message = format!("{}: no `*.test` files found", dir.display());
+            self.trials
+                .push(Trial::test(dir.to_string_lossy(), move || {
+                    Err(message.into())
+                }));
+        }
+        for file in files {
+            let path = match file {
+                Ok(path) => path,
+                Err((path, message)) => {
+                    self.trials
+                        .push(Trial::test(path, move || Err(message.into())));
+                    continue;
+                }
+            };
+            let file_name = path.strip_prefix(dir).unwrap_or(&path).with_extension("");
+            let file_name = file_name.to_string_lossy().replace('\\', "/");
+            let path = Arc::new(path.to_string_lossy().into_owned());
+            let cases = match fs::read_to_string(&*path) {
+                Ok(source) => conformance_file::parse(&source).map_err(|errors| {
+                    let errors = errors
+                        .iter()
+                        .map(|e| format!("{}:{}: {}", path, e.line, e.message));
+                    errors.collect::<Vec<_>>().join("\n")
+                }),
+                Err(e) => Err(format!("{}: failed to read file: {}", path, e)),
+            };
+            let cases = match cases {
+                Ok(cases) => cases,
+                Err(message) => {
+                    self.trials
+                        .push(Trial::test(file_name, move || Err(message.into())));
+                    continue;
+                }
+            };
+            let only = cases.iter().any(|case| case.directives.only);
+            for case in cases {
+                let path = Arc::clone(&path);
+                let format = format.clone();
+                let name = format!("{}::{}", file_name, case.name);
+                let ignored = case.directives.ignore.is_some() || (only && !case.directives.only);
+                let kind = case.directives.tags.join(",");
+                let should_panic = case.directives.should_panic.clone();
+                let check = move |bless| {
+                    let section = match case.section(None) {
+                        Some(section) => section,
+                        None => return Err("named `---` sections are not supported".into()),
+                    };
+                    if section.outcome != Outcome::Ok {
+                        let message = format!(
+                            "`{}` outputs are not supported",
+                            section.outcome.separator()
+                        );
+                        return Err(message.into());
+                    }
+                    let actual = format.serialize(&test(&case.input)).map_err(fail)?;
+                    let expected = format.normalize(&section.output);
+                    if bless && env::var_os(crate::BLESS_VAR).is_some() {
+                        if !matches!(&expected, Ok(e) if *e == actual) {
+                            crate::bless(&path, &case.name, None, Outcome::Ok, &actual)
+                                .map_err(fail)?;
+                        }
+                        return Ok(());
+                    }
+                    let expected = expected.map_err(fail)?;
+                    match crate::mismatch(&path, case.line, &expected, &actual) {
+                        Some(message) => Err(message.into()),
+                        None => Ok(()),
+                    }
+                };
+                let trial = match should_panic {
+                    None => Trial::test(name, move || check(true)),
+                    // Errors count as panics, and the output is not blessed,
+                    // as it is not expected to match.
+                    Some(expected) => Trial::test(name, move || {
+                        let message = match panic::catch_unwind(AssertUnwindSafe(|| check(false))) {
+                            Ok(Ok(())) => return Err("test did not panic as expected".into()),
+                            Ok(Err(failed)) => failed.message().unwrap_or_default().to_string(),
+                            Err(payload) => panic_message(&*payload),
+                        };
+                        match expected {
+                            Some(expected) if !message.contains(&expected) => Err(format!(
+                                "panic did not contain expected string\n      \
+                                 panic message: {:?}\n expected substring: {:?}",
+                                message, expected,
+                            )
+                            .into()),
+                            _ => Ok(()),
+                        }
+                    }),
+                };
+                self.trials
+                    .push(trial.with_ignored_flag(ignored).with_kind(kind));
+            }
+        }
+        self
+    }
+
+    /// Run the tests as the standard test harness would, and exit the process.
+    pub fn run(&mut self) -> ! {
+        let args = Arguments::from_args();
+        let trials = std::mem::take(&mut self.trials);
+        libtest_mimic::run(&args, trials).exit()
+    }
+}
+
+fn fail(e: impl ToString) -> Failed {
+    e.to_string().into()
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_default(),
+    }
+}
+
+/// The `*.test` files anywhere under `dir`, in a stable order,
+/// and the path and error message of anything that could not be read while looking for them.
+fn test_files(dir: &Path) -> Vec<Result<PathBuf, (String, String)>> {
+    WalkDir::new(dir)
+        .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Ok(entry) => {
+                let is_test = entry.path().extension().is_some_and(|ext| ext == "test");
+                if entry.file_type().is_file() && is_test {
+                    Some(Ok(entry.into_path()))
+                } else {
+                    None
+                }
+            }
+            Err(e) => {
+                let path = e.path().unwrap_or(dir).to_string_lossy().into_owned();
+                let message = format!("failed to read directory: {}", e);
+                Some(Err((path, message)))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::format::Json, std::process};
+
+    /// An empty directory for the test `name`, to remove when done.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("conformance-{}-{}", name, process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// The number of tests in `runner` that fail, logging the run in `dir`.
+    fn failures(runner: &mut Runner, dir: &Path) -> u64 {
+        let args = Arguments {
+            test_threads: Some(1),
+            logfile: Some(dir.join("log").to_string_lossy().into_owned()),
+            ..Arguments::default()
+        };
+        let trials = std::mem::take(&mut runner.trials);
+        libtest_mimic::run(&args, trials).num_failed
+    }
+
+    #[test]
+    fn missing_dir() {
+        let dir = temp_dir("missing");
+        let mut runner = Runner::new();
+        runner.exact(dir.join("does not exist"), Json, str::len);
+        assert_eq!(runner.trials.len(), 1);
+        assert_eq!(failures(&mut runner, &dir), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn no_test_files() {
+        let dir = temp_dir("empty");
+        fs::write(dir.join("README"), "not a test file").unwrap();
+        let mut runner = Runner::new();
+        runner.exact(&dir, Json, str::len);
+        assert_eq!(runner.trials.len(), 1);
+        assert_eq!(failures(&mut runner, &dir), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,358 @@
+//! Matching of actual output against a partial expected structure.
+//!
+//! In `superset` mode, both the expected and the actual output are deserialized
+//! into a [`Value`], and the actual value must contain the expected one:
+//!
+//! - a map contains another if it has all of its keys,
+//!   and the values of those keys contain the other's values;
+//! - a sequence contains another if a prefix of it contains the other's elements in order,
+//!   where an element `..` in the expected sequence stands for any number of elements;
+//! - anything contains the expected value `_`;
+//! - any other value contains only an equal value.
+//!
+//! This requires a self-describing format, such as JSON or YAML.
+
+use {
+    serde::{
+        de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor},
+        ser::{Serialize, Serializer},
+    },
+    std::{convert::TryFrom, fmt},
+};
+
+/// A value of the serde data model, as produced by a self-describing format.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Unit,
+    Bool(bool),
+    Integer(i128),
+    Float(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    Seq(Vec<Value>),
+    /// The entries of a map, in order.
+    Map(Vec<(Value, Value)>),
+}
+
+impl Value {
+    /// Does this value contain the partial structure `expected`?
+    pub fn contains(&self, expected: &Value) -> bool {
+        match (self, expected) {
+            (_, Value::String(wildcard)) if wildcard == "_" => true,
+            (Value::Map(actual), Value::Map(expected)) => expected.iter().all(|(key, expected)| {
+                actual
+                    .iter()
+                    .any(|(k, actual)| k == key && actual.contains(expected))
+            }),
+            (Value::Seq(actual), Value::Seq(expected)) => seq_contains(actual, expected),
+            (actual, expected) => actual == expected,
+        }
+    }
+}
+
+/// Where an actual value does not contain the expected one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch<'a> {
+    /// The keys and indices that lead to the values from the root, like `.Program[1]`,
+    /// or empty if they are the root values.
+    /// A range like `.Program[1..]` is where no element could be the expected one.
+    pub path: String,
+    /// The part of the expected value that is not contained.
+    pub expected: &'a Value,
+    /// The value at the same place in the actual value, or `None` if there is none.
+    pub actual: Option<&'a Value>,
+}
+
+impl Value {
+    /// Find the innermost part of the partial structure `expected` that this value does not contain.
+    ///
+    /// Where an expected sequence skips elements with `..`, an element that is not contained
+    /// is compared to the next actual element that is the same kind of value.
+    pub fn mismatch<'a>(&'a self, expected: &'a Value) -> Option<Mismatch<'a>> {
+        if self.contains(expected) {
+            return None;
+        }
+        let here = || Mismatch {
+            path: String::new(),
+            expected,
+            actual: Some(self),
+        };
+        let (path, mismatch) = match (self, expected) {
+            (Value::Map(actual), Value::Map(expected)) => {
+                let (key, expected) = expected
+                    .iter()
+                    .find(|(key, expected)| {
+                        !actual
+                            .iter()
+                            .any(|(k, actual)| k == key && actual.contains(expected))
+                    })
+                    .unwrap();
+                let path = match key {
+                    Value::String(key) => format!(".{}", key),
+                    key => format!("[{:?}]", key),
+                };
+                let mismatch = match actual.iter().find(|(k, _)| k == key) {
+                    Some((_, actual)) => actual.mismatch(expected).unwrap(),
+                    None => Mismatch {
+                        path: String::new(),
+                        expected,
+                        actual: None,
+                    },
+                };
+                (path, mismatch)
+            }
+            (Value::Seq(actual), Value::Seq(elements)) => {
+                // the longest start of the expected elements that is contained, and where it ends
+                let (matched, end) = (0..elements.len())
+                    .rev()
+                    .find_map(|len| Some((len, seq_match_end(actual, &elements[..len], 0)?)))
+                    .unwrap();
+                let expected = &elements[matched];
+                let skipped = matched > 0 && is_rest(&elements[matched - 1]);
+                let i = if skipped {
+                    // the next element that could be the expected one
+                    (end..actual.len()).find(|&i| actual[i].same_shape(expected))
+                } else {
+                    Some(end).filter(|&i| i < actual.len())
+                };
+                match i {
+                    Some(i) => (format!("[{}]", i), actual[i].mismatch(expected).unwrap()),
+                    None => (
+                        // none of the elements from `end` on could be it
+                        format!("[{}{}]", end, if skipped { ".." } else { "" }),
+                        Mismatch {
+                            path: String::new(),
+                            expected,
+                            actual: None,
+                        },
+                    ),
+                }
+            }
+            _ => return Some(here()),
+        };
+        Some(Mismatch {
+            path: path + &mismatch.path,
+            ..mismatch
+        })
+    }
+
+    /// Is this the same kind of value as `other`, and a map with the same keys if it is a map?
+    fn same_shape(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Map(a), Value::Map(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|((a, _), (b, _))| a == b)
+            }
+            (a, b) => std::mem::discriminant(a) == std::mem::discriminant(b),
+        }
+    }
+}
+
+fn is_rest(value: &Value) -> bool {
+    matches!(value, Value::String(rest) if rest == "..")
+}
+
+/// Where the first of the `actual` elements from `start` that contain the `expected` ones ends,
+/// if they do.
+fn seq_match_end(actual: &[Value], expected: &[Value], start: usize) -> Option<usize> {
+    match expected.split_first() {
+        None => Some(start),
+        Some((rest, expected)) if is_rest(rest) => {
+            (start..=actual.len()).find_map(|skip| seq_match_end(actual, expected, skip))
+        }
+        Some((first, expected)) => match actual.get(start) {
+            Some(head) if head.contains(first) => seq_match_end(actual, expected, start + 1),
+            _ => None,
+        },
+    }
+}
+
+fn seq_contains(actual: &[Value], expected: &[Value]) -> bool {
+    match expected.split_first() {
+        None => true,
+        Some((Value::String(rest), expected)) if rest == ".." => {
+            (0..=actual.len()).any(|skip| seq_contains(&actual[skip..], expected))
+        }
+        Some((first, expected)) => match actual.split_first() {
+            Some((head, actual)) => head.contains(first) && seq_contains(actual, expected),
+            None => false,
+        },
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Value::Unit => serializer.serialize_unit(),
+            Value::Bool(v) => serializer.serialize_bool(*v),
+            Value::Integer(v) => match i64::try_from(*v) {
+                Ok(v) => serializer.serialize_i64(v),
+                Err(_) => serializer.serialize_i128(*v),
+            },
+            Value::Float(v) => serializer.serialize_f64(*v),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::Bytes(v) => serializer.serialize_bytes(v),
+            Value::Seq(v) => serializer.collect_seq(v),
+            Value::Map(v) => serializer.collect_map(v.iter().map(|(k, v)| (k, v))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "any value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<Value, E> {
+        Ok(Value::Integer(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::Bytes(v.to_vec()))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Unit)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Unit)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut values = vec![];
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Value::Seq(values))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entries = vec![];
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Value::Map(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Value::{self, *};
+
+    fn s(s: &str) -> Value {
+        String(s.to_string())
+    }
+
+    fn map(entries: &[(&str, Value)]) -> Value {
+        Map(entries.iter().map(|(k, v)| (s(k), v.clone())).collect())
+    }
+
+    #[test]
+    fn contains() {
+        let actual = map(&[("a", Integer(1)), ("b", Seq(vec![s("x"), s("y"), s("z")]))]);
+        assert!(actual.contains(&map(&[])));
+        assert!(actual.contains(&map(&[("b", Seq(vec![s("x")]))])));
+        assert!(actual.contains(&map(&[("a", s("_")), ("b", s("_"))])));
+        assert!(actual.contains(&map(&[("b", Seq(vec![s(".."), s("z")]))])));
+        assert!(actual.contains(&map(&[("b", Seq(vec![s("x"), s(".."), s("y")]))])));
+        assert!(!actual.contains(&map(&[("c", s("_"))])));
+        assert!(!actual.contains(&map(&[("a", Integer(2))])));
+        assert!(!actual.contains(&map(&[("b", Seq(vec![s("y")]))])));
+        assert!(!actual.contains(&map(&[("b", Seq(vec![s(".."), s("w")]))])));
+        assert!(!actual.contains(&Seq(vec![])));
+    }
+
+    #[test]
+    fn mismatch() {
+        let actual = map(&[
+            ("a", Integer(1)),
+            ("b", Seq(vec![s("x"), map(&[("c", Integer(2))])])),
+        ]);
+        assert_eq!(actual.mismatch(&map(&[("a", s("_"))])), None);
+        let expected = map(&[("b", Seq(vec![s("_"), map(&[("c", Integer(3))])]))]);
+        let mismatch = actual.mismatch(&expected).unwrap();
+        assert_eq!(mismatch.path, ".b[1].c");
+        assert_eq!(mismatch.expected, &Integer(3));
+        assert_eq!(mismatch.actual, Some(&Integer(2)));
+        let expected = map(&[("d", Unit)]);
+        let mismatch = actual.mismatch(&expected).unwrap();
+        assert_eq!((&*mismatch.path, mismatch.actual), (".d", None));
+        // after `..`, the next element of the same kind
+        let expected = map(&[("b", Seq(vec![s(".."), s("y")]))]);
+        let mismatch = actual.mismatch(&expected).unwrap();
+        assert_eq!((&*mismatch.path, mismatch.actual), (".b[0]", Some(&s("x"))));
+        let expected = map(&[("b", Seq(vec![s(".."), map(&[("c", Integer(3))])]))]);
+        let mismatch = actual.mismatch(&expected).unwrap();
+        assert_eq!(
+            (&*mismatch.path, mismatch.actual),
+            (".b[1].c", Some(&Integer(2)))
+        );
+        let expected = map(&[("b", Seq(vec![s(".."), Integer(3)]))]);
+        let mismatch = actual.mismatch(&expected).unwrap();
+        assert_eq!((&*mismatch.path, mismatch.actual), (".b[0..]", None));
+        let expected = map(&[("b", Seq(vec![s("_"), s("_"), s("z")]))]);
+        let mismatch = actual.mismatch(&expected).unwrap();
+        assert_eq!((&*mismatch.path, mismatch.expected), (".b[2]", &s("z")));
+        assert_eq!(mismatch.actual, None);
+    }
+}
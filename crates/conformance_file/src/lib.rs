@@ -0,0 +1,438 @@
+//! The format of conformance test files.
+//!
+//! A test file is a list of tests, each of which is made of lines:
+//!
+//! - any number of directives, each on a line starting with `@`;
+//! - the name of the test;
+//! - `===`;
+//! - the input, on any number of lines;
+//! - a separator: `---`, `--- Err`, or `--- Err message`;
+//! - the expected output, on any number of lines;
+//! - `...`.
+//!
+//! Blank lines between tests are ignored. The input ends at the last separator of the test,
+//! so it may contain separator lines itself, but neither it nor the output can contain `...`.
+//! The input and output are trimmed, except for the input of a `@raw` test,
+//! which is everything between the `===` and separator lines, as is.
+//!
+//! A test can instead have several outputs, for different functions, in named sections.
+//! The separator of each names its section after the `---`, as in `--- tokens` or
+//! `--- tree Err message`. The input of such a test ends at the first named separator,
+//! and each output at the next one.
+
+use std::{collections::HashSet, fmt, ops::Range};
+
+/// One test in a test file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Test {
+    /// The name of the test, with spaces replaced by underscores.
+    pub name: String,
+    /// The one-based line the test starts on, which is that of its first directive if any.
+    pub line: usize,
+    pub directives: Directives,
+    pub input: String,
+    /// The expected outputs: one without a name, or any number of named sections.
+    pub sections: Vec<Section>,
+}
+
+impl Test {
+    /// The section named `name`, or the unnamed one.
+    pub fn section(&self, name: Option<&str>) -> Option<&Section> {
+        self.sections
+            .iter()
+            .find(|section| section.name.as_deref() == name)
+    }
+}
+
+/// One expected output of a test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// The name after the `---` of the separator, if any.
+    pub name: Option<String>,
+    pub outcome: Outcome,
+    pub output: String,
+    /// The byte range of the separator line and the output, up to the next separator or `...`.
+    pub range: Range<usize>,
+}
+
+/// The `@` lines before the name of a test, which change how it is run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Directives {
+    /// `@ignore`, with an optional reason.
+    pub ignore: Option<Option<String>>,
+    /// `@should_panic`, with an optional substring of the expected panic message.
+    pub should_panic: Option<Option<String>>,
+    /// `@only`: ignore the other tests in the file.
+    pub only: bool,
+    /// `@raw`: do not trim the input.
+    pub raw: bool,
+    /// `@tag`s, with spaces replaced by underscores.
+    pub tags: Vec<String>,
+}
+
+impl Directives {
+    /// Add the directive on a line, which starts with `@`.
+    fn add(&mut self, line: &str) -> Result<(), String> {
+        let line = line.trim().trim_start_matches('@');
+        let (directive, arg) = match line.find(char::is_whitespace) {
+            Some(ix) => (&line[..ix], Some(line[ix..].trim().to_string())),
+            None => (line, None),
+        };
+        match (directive, arg) {
+            ("ignore", arg) => self.ignore = Some(arg),
+            ("should_panic", arg) => self.should_panic = Some(arg),
+            ("only", None) => self.only = true,
+            ("raw", None) => self.raw = true,
+            ("tag", Some(tag)) => self.tags.push(tag.replace(' ', "_")),
+            ("only", Some(_)) | ("raw", Some(_)) => {
+                return Err(format!("`@{}` does not take an argument", directive))
+            }
+            ("tag", None) => return Err("`@tag` needs a name".into()),
+            (directive, _) => return Err(format!("unknown directive `@{}`", directive)),
+        }
+        Ok(())
+    }
+}
+
+/// Which result a test expects, as given by the line between its input and output.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    /// `---`: the serialized return value, or `Ok` value.
+    Ok,
+    /// `--- Err`: the serialized `Err` value.
+    Err,
+    /// `--- Err message`: the `Display` text of the `Err` value.
+    ErrMessage,
+}
+
+impl Outcome {
+    /// The line between the input and output of a test with this outcome.
+    pub fn separator(self) -> &'static str {
+        match self {
+            Outcome::Ok => "---",
+            Outcome::Err => "--- Err",
+            Outcome::ErrMessage => "--- Err message",
+        }
+    }
+
+    /// The section name and outcome of a separator line, without its line break.
+    fn from_separator(line: &str) -> Option<(Option<String>, Outcome)> {
+        let rest = line.trim_end().strip_prefix("---")?;
+        if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let mut words: Vec<&str> = rest.split_whitespace().collect();
+        let outcome = match words[..] {
+            [.., "Err", "message"] => Outcome::ErrMessage,
+            [.., "Err"] => Outcome::Err,
+            _ => Outcome::Ok,
+        };
+        words.truncate(match outcome {
+            Outcome::Ok => words.len(),
+            Outcome::Err => words.len() - 1,
+            Outcome::ErrMessage => words.len() - 2,
+        });
+        match words[..] {
+            [] => Some((None, outcome)),
+            [name] if name.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+                Some((Some(name.to_string()), outcome))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The separator line before an output with this `outcome`, in the section `name` if any.
+pub fn separator(name: Option<&str>, outcome: Outcome) -> String {
+    match name {
+        Some(name) => outcome
+            .separator()
+            .replacen("---", &format!("--- {}", name), 1),
+        None => outcome.separator().to_string(),
+    }
+}
+
+/// A malformed test in a test file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// The one-based line of the error.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A line of a test file, without its line break.
+#[derive(Copy, Clone)]
+struct Line<'a> {
+    /// The byte offset of the start of the line.
+    start: usize,
+    text: &'a str,
+}
+
+impl Line<'_> {
+    /// Is this line the marker `marker`, ignoring trailing whitespace?
+    fn is(&self, marker: &str) -> bool {
+        self.text.trim_end() == marker
+    }
+}
+
+/// Parse the tests in the source of a test file.
+///
+/// Every malformed test is reported, not only the first.
+pub fn parse(source: &str) -> Result<Vec<Test>, Vec<Error>> {
+    let mut lines: Vec<Line<'_>> = vec![];
+    let mut start = 0;
+    for text in source.split_inclusive('\n') {
+        lines.push(Line {
+            start,
+            text: text.strip_suffix('\n').unwrap_or(text),
+        });
+        start += text.len();
+    }
+
+    let mut tests = vec![];
+    let mut errors = vec![];
+    let mut names = HashSet::new();
+    let mut i = 0;
+    let mut error = |line: usize, message: String| errors.push(Error { line, message });
+    while i < lines.len() {
+        if lines[i].text.trim().is_empty() {
+            i += 1;
+            continue;
+        }
+        let first = i;
+        // The index of the line after the end of this test, where the next one starts.
+        let next = lines[i..]
+            .iter()
+            .position(|line| line.is("..."))
+            .map_or(lines.len(), |ix| i + ix + 1);
+
+        let mut directives = Directives::default();
+        let mut malformed = false;
+        while i < next && lines[i].text.trim_start().starts_with('@') {
+            if let Err(message) = directives.add(lines[i].text) {
+                error(i + 1, message);
+                malformed = true;
+            }
+            i += 1;
+        }
+
+        let name = lines.get(i).map_or("", |line| line.text.trim());
+        if name.is_empty() || ["===", "---", "..."].contains(&name) {
+            error(i + 1, "expected the name of a test".to_string());
+            i = next;
+            continue;
+        }
+        let name = name.replace(' ', "_");
+        if !names.insert(name.clone()) {
+            error(i + 1, format!("there is already a test named `{}`", name));
+            malformed = true;
+        }
+        i += 1;
+
+        if !lines.get(i).is_some_and(|line| line.is("===")) {
+            error(
+                i,
+                format!("expected `===` after the name of test `{}`", name),
+            );
+            i = next;
+            continue;
+        }
+        let equals = i;
+        i += 1;
+
+        if !lines[next - 1].is("...") {
+            error(
+                first + 1,
+                format!("test `{}` does not end with `...`", name),
+            );
+            break;
+        }
+        let dots = next - 1;
+        let separators: Vec<_> = (i..dots)
+            .filter_map(|ix| Outcome::from_separator(lines[ix].text).map(|(n, o)| (ix, n, o)))
+            .collect();
+        // Named sections start at the first named separator, and an unnamed one at the last.
+        let separators = match separators.iter().position(|(_, name, _)| name.is_some()) {
+            Some(first) => separators[first..]
+                .iter()
+                .filter(|(_, name, _)| name.is_some())
+                .cloned()
+                .collect(),
+            None => separators.last().cloned().into_iter().collect::<Vec<_>>(),
+        };
+        if separators.is_empty() {
+            error(
+                equals + 1,
+                format!("test `{}` does not have `---` after its input", name),
+            );
+            i = next;
+            continue;
+        }
+
+        let mut sections: Vec<Section> = vec![];
+        for (ix, &(separator, ref section, outcome)) in separators.iter().enumerate() {
+            if sections.iter().any(|s| s.name == *section) {
+                let section = section.as_deref().unwrap_or_default();
+                error(
+                    separator + 1,
+                    format!("test `{}` already has a section `{}`", name, section),
+                );
+                malformed = true;
+            }
+            let end = separators.get(ix + 1).map_or(dots, |&(end, ..)| end);
+            sections.push(Section {
+                name: section.clone(),
+                outcome,
+                output: source[lines[separator + 1].start..lines[end].start]
+                    .trim()
+                    .to_string(),
+                range: lines[separator].start..lines[end].start,
+            });
+        }
+
+        if malformed {
+            i = next;
+            continue;
+        }
+
+        let input = &source[lines[i].start..lines[separators[0].0].start];
+        let input = if directives.raw {
+            let input = input.strip_suffix('\n').unwrap_or(input);
+            input.strip_suffix('\r').unwrap_or(input)
+        } else {
+            input.trim()
+        };
+
+        tests.push(Test {
+            name,
+            line: first + 1,
+            directives,
+            input: input.to_string(),
+            sections,
+        });
+        i = next;
+    }
+
+    if errors.is_empty() {
+        Ok(tests)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests() {
+        let source = "\
+one
+===
+ a
+---
+ b
+...
+
+@raw
+@tag two words
+two
+===
+  a
+
+---
+ ---
+--- Err message
+...
+
+three
+===
+a
+---
+b
+--- tokens
+c
+---
+d
+--- tree Err
+e
+...
+";
+        let tests = parse(source).unwrap();
+        assert_eq!(tests.len(), 3);
+        let one = tests[0].section(None).unwrap();
+        assert_eq!(
+            (tests[0].line, &*tests[0].input, &*one.output),
+            (1, "a", "b")
+        );
+        assert_eq!(&source[one.range.clone()], "---\n b\n");
+
+        let two = &tests[1];
+        assert_eq!((two.line, &*two.name), (8, "two"));
+        assert_eq!(two.sections.len(), 1);
+        assert_eq!(two.sections[0].outcome, Outcome::ErrMessage);
+        assert!(two.directives.raw);
+        assert_eq!(two.directives.tags, ["two_words"]);
+        assert_eq!(two.input, "  a\n\n---\n ---");
+        assert_eq!(two.sections[0].output, "");
+
+        let three = &tests[2];
+        assert_eq!(three.input, "a\n---\nb");
+        assert!(three.section(None).is_none());
+        let tokens = three.section(Some("tokens")).unwrap();
+        let tree = three.section(Some("tree")).unwrap();
+        assert_eq!(
+            (tokens.outcome, &*tokens.output),
+            (Outcome::Ok, "c\n---\nd")
+        );
+        assert_eq!((tree.outcome, &*tree.output), (Outcome::Err, "e"));
+        assert_eq!(&source[tree.range.clone()], "--- tree Err\ne\n");
+        assert_eq!(separator(Some("tree"), Outcome::Err), "--- tree Err");
+    }
+
+    #[test]
+    fn errors() {
+        let source = "\
+one
+---
+a
+...
+
+two
+===
+a
+...
+
+@ignore
+@bogus
+three
+===
+---
+...
+
+two
+===
+---
+...
+
+five
+===
+--- tree
+--- tree
+...
+
+four
+===
+";
+        let lines = |errors: Vec<Error>| errors.iter().map(|e| e.line).collect::<Vec<_>>();
+        assert_eq!(lines(parse(source).unwrap_err()), [1, 7, 12, 18, 26, 29]);
+    }
+}
@@ -0,0 +1,624 @@
+extern crate proc_macro;
+
+use {
+    conformance_file::Outcome,
+    proc_macro2::{Span, TokenStream},
+    quote::{quote, quote_spanned},
+    std::{
+        env,
+        fs::File,
+        io::prelude::*,
+        path::{Path, PathBuf},
+    },
+    syn::parse::Parse,
+};
+
+fn compile_error(s: &str, span: Span) -> TokenStream {
+    quote_spanned!(span=> compile_error! { #s })
+}
+
+/// How actual output is compared to the expected output.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Mode {
+    /// The normalized outputs must be equal.
+    Exact,
+    /// The actual output must contain the partial structure of the expected output.
+    Superset,
+}
+
+/// How output is serialized and deserialized.
+enum Codec {
+    /// `ser` and `de` functions, or those of a `serde` format crate.
+    Functions {
+        ser: syn::ExprPath,
+        de: syn::ExprPath,
+    },
+    /// A value that implements `conformance::Format`.
+    Format(TokenStream),
+}
+
+impl Codec {
+    /// The stock format named `name`, if there is one.
+    fn stock(name: &syn::Ident) -> Option<syn::Ident> {
+        let format = match &*name.to_string() {
+            "json" => "Json",
+            "json_pretty" => "JsonPretty",
+            "yaml" => "Yaml",
+            "ron" => "Ron",
+            "ron_pretty" => "RonPretty",
+            "sexp" => "Sexp",
+            "sexp_pretty" => "SexpPretty",
+            _ => return None,
+        };
+        Some(syn::Ident::new(format, name.span()))
+    }
+
+    /// An expression serializing `value`, a reference, to a `Result<String, _>`.
+    fn ser(&self, value: TokenStream) -> TokenStream {
+        match self {
+            Codec::Functions { ser, .. } => quote!(#ser(#value)),
+            Codec::Format(format) => quote!(::conformance::Format::serialize(&#format, #value)),
+        }
+    }
+
+    /// An expression deserializing a `ty` from `s` to a `Result<ty, _>`.
+    fn de(&self, ty: &syn::Type, s: TokenStream) -> TokenStream {
+        match self {
+            Codec::Functions { de, .. } => quote!(#de::<#ty>(#s)),
+            Codec::Format(format) => {
+                quote!(::conformance::Format::deserialize::<#ty>(&#format, #s))
+            }
+        }
+    }
+
+    /// An expression normalizing the serialized `s` to a `String`, returning early on errors,
+    /// by de- and reserializing it as a `ty`, or with the format's own normalization.
+    fn normalize(&self, ty: Option<&syn::Type>, s: TokenStream) -> TokenStream {
+        match (self, ty) {
+            (_, Some(ty)) => {
+                let de = self.de(ty, s);
+                let ser = self.ser(quote!(&#de?));
+                quote!(#ser?)
+            }
+            (Codec::Format(format), None) => {
+                quote!(::conformance::Format::normalize(&#format, #s)?)
+            }
+            (Codec::Functions { .. }, None) => unreachable!("functions need a value type"),
+        }
+    }
+}
+
+struct AttrArgs {
+    mode: Mode,
+    codec: Codec,
+    value: Option<syn::Type>,
+    file: syn::LitStr,
+    section: Option<syn::Ident>,
+}
+
+impl Parse for AttrArgs {
+    fn parse(input: &syn::parse::ParseBuffer<'_>) -> syn::parse::Result<Self> {
+        mod kw {
+            syn::custom_keyword!(exact);
+            syn::custom_keyword!(superset);
+            syn::custom_keyword!(file);
+            syn::custom_keyword!(ser);
+            syn::custom_keyword!(de);
+            syn::custom_keyword!(value);
+            syn::custom_keyword!(serde);
+            syn::custom_keyword!(format);
+            syn::custom_keyword!(section);
+        }
+
+        let la = input.lookahead1();
+        let mode = if la.peek(kw::exact) {
+            let _: kw::exact = input.parse()?;
+            Mode::Exact
+        } else if la.peek(kw::superset) {
+            let _: kw::superset = input.parse()?;
+            Mode::Superset
+        } else {
+            return Err(la.error());
+        };
+        let _: syn::Token![,] = input.parse()?;
+
+        let la = input.lookahead1();
+        let (codec, value) = if la.peek(kw::format) {
+            let _: kw::format = input.parse()?;
+            let _: syn::Token![=] = input.parse()?;
+            let format: syn::Path = input.parse()?;
+            let _: syn::Token![,] = input.parse()?;
+
+            let value = if input.peek(kw::value) {
+                let _: kw::value = input.parse()?;
+                let _: syn::Token![=] = input.parse()?;
+                let value: syn::Type = input.parse()?;
+                let _: syn::Token![,] = input.parse()?;
+                Some(value)
+            } else {
+                None
+            };
+
+            if format.is_ident("text") {
+                // not a `Format`, as it works through `Display` and `FromStr` instead of serde
+                let ser = syn::parse_quote!(::conformance::format::text::to_string);
+                let de = syn::parse_quote!(::conformance::format::text::from_str);
+                (Codec::Functions { ser, de }, value)
+            } else {
+                let format = match format.get_ident().and_then(Codec::stock) {
+                    Some(stock) => quote!(::conformance::format::#stock),
+                    None => quote!(#format),
+                };
+                (Codec::Format(format), value)
+            }
+        } else if la.peek(kw::serde) {
+            let _: kw::serde = input.parse()?;
+            let _: syn::Token![=] = input.parse()?;
+            let format: syn::ExprPath = input.parse()?;
+            let _: syn::Token![,] = input.parse()?;
+
+            // FUTURE(rust-lang/rust#64797): use #[cfg(accessible)] to prefer `to_string_pretty`
+            // as well as fall back to the `::ser::to_string`/`::de::from_str`/`::value::Value`
+
+            // for errors
+            let la = input.lookahead1();
+            if !(la.peek(kw::ser) || la.peek(kw::de) || la.peek(kw::value) | la.peek(kw::file)) {
+                return Err(la.error());
+            }
+
+            let ser: syn::ExprPath = if input.peek(kw::ser) {
+                let _: kw::ser = input.parse()?;
+                let _: syn::Token![=] = input.parse()?;
+                let ser: syn::ExprPath = input.parse()?;
+                let _: syn::Token![,] = input.parse()?;
+
+                // for errors
+                let la = input.lookahead1();
+                if !(la.peek(kw::de) || la.peek(kw::value) | la.peek(kw::file)) {
+                    return Err(la.error());
+                }
+
+                ser
+            } else {
+                syn::parse_quote!(#format::to_string)
+            };
+
+            let de: syn::ExprPath = if input.peek(kw::de) {
+                let _: kw::de = input.parse()?;
+                let _: syn::Token![=] = input.parse()?;
+                let de: syn::ExprPath = input.parse()?;
+                let _: syn::Token![,] = input.parse()?;
+
+                // for errors
+                let la = input.lookahead1();
+                if !(la.peek(kw::value) | la.peek(kw::file)) {
+                    return Err(la.error());
+                }
+
+                de
+            } else {
+                syn::parse_quote!(#format::from_str)
+            };
+
+            let value: syn::Type = if input.peek(kw::value) {
+                let _: kw::value = input.parse()?;
+                let _: syn::Token![=] = input.parse()?;
+                let value: syn::Type = input.parse()?;
+                let _: syn::Token![,] = input.parse()?;
+                value
+            } else {
+                syn::parse_quote!(#format::Value)
+            };
+
+            (Codec::Functions { ser, de }, Some(value))
+        } else if la.peek(kw::ser) {
+            let _: kw::ser = input.parse()?;
+            let _: syn::Token![=] = input.parse()?;
+            let ser: syn::ExprPath = input.parse()?;
+            let _: syn::Token![,] = input.parse()?;
+
+            let _: kw::de = input.parse()?;
+            let _: syn::Token![=] = input.parse()?;
+            let de: syn::ExprPath = input.parse()?;
+            let _: syn::Token![,] = input.parse()?;
+
+            let value = if input.peek(kw::value) {
+                let _: kw::value = input.parse()?;
+                let _: syn::Token![=] = input.parse()?;
+                let value: syn::Type = input.parse()?;
+                let _: syn::Token![,] = input.parse()?;
+                Some(value)
+            } else {
+                None
+            };
+
+            (Codec::Functions { ser, de }, value)
+        } else {
+            return Err(la.error());
+        };
+
+        let _: kw::file = input.parse()?;
+        let _: syn::Token![=] = input.parse()?;
+        let file: syn::LitStr = input.parse()?;
+
+        let section = if input.peek(syn::Token![,]) {
+            let _: syn::Token![,] = input.parse()?;
+            let _: kw::section = input.parse()?;
+            let _: syn::Token![=] = input.parse()?;
+            let section: syn::Ident = input.parse()?;
+            Some(section)
+        } else {
+            None
+        };
+
+        Ok(AttrArgs {
+            mode,
+            codec,
+            value,
+            file,
+            section,
+        })
+    }
+}
+
+/// A test, with the identifier its test function is named with, and the section it checks.
+struct Test {
+    ident: syn::Ident,
+    test: conformance_file::Test,
+    section: conformance_file::Section,
+}
+
+/// The tests in the file that have the `section`, or the unnamed one.
+fn read_tests(
+    file_path: &Path,
+    display_path: &str,
+    section: Option<&str>,
+    span: Span,
+) -> Result<Vec<Test>, TokenStream> {
+    let source = {
+        let mut f = File::open(file_path)
+            .map_err(|e| compile_error(&format!("failed to open file: {}", e), span))?;
+        let mut s = String::with_capacity(f.metadata().map(|m| m.len() as usize + 1).unwrap_or(0));
+        f.read_to_string(&mut s)
+            .map_err(|e| compile_error(&format!("failed to read file: {}", e), span))?;
+        s
+    };
+
+    // The span of a string literal cannot point into the file, so errors say where they are.
+    let error = |line: usize, message: &str| {
+        compile_error(&format!("{}:{}: {}", display_path, line, message), span)
+    };
+    let tests = conformance_file::parse(&source).map_err(|errors| {
+        errors
+            .iter()
+            .map(|e| error(e.line, &e.message))
+            .collect::<TokenStream>()
+    })?;
+
+    let mut errs = TokenStream::new();
+    let mut idents = Vec::new();
+    for test in tests {
+        let test_section = match (test.section(section), section) {
+            (Some(test_section), _) => test_section.clone(),
+            // Tests without the section are for other functions.
+            (None, Some(_)) => continue,
+            (None, None) => {
+                errs.extend(error(
+                    test.line,
+                    &format!(
+                        "test `{}` has only named sections; choose one with `section = ...`",
+                        test.name,
+                    ),
+                ));
+                continue;
+            }
+        };
+        match syn::parse_str::<syn::Ident>(&format!("_{}", test.name)) {
+            Ok(ident) => idents.push(Test {
+                ident,
+                test,
+                section: test_section,
+            }),
+            Err(_) => errs.extend(error(
+                test.line,
+                &format!("`{}` is not a valid test name identifier", test.name),
+            )),
+        }
+    }
+    if let (Some(section), true) = (section, idents.is_empty()) {
+        errs.extend(compile_error(
+            &format!(
+                "no test in {} has a `--- {}` section",
+                display_path, section
+            ),
+            span,
+        ));
+    }
+
+    if errs.is_empty() {
+        Ok(idents)
+    } else {
+        Err(errs)
+    }
+}
+
+fn outcome_tokens(outcome: Outcome) -> TokenStream {
+    match outcome {
+        Outcome::Ok => quote!(::conformance::Outcome::Ok),
+        Outcome::Err => quote!(::conformance::Outcome::Err),
+        Outcome::ErrMessage => quote!(::conformance::Outcome::ErrMessage),
+    }
+}
+
+/// The `T` and `E` of a `Result<T, E>` type.
+fn result_types(r#type: &syn::Type) -> Option<(syn::Type, syn::Type)> {
+    let path = match r#type {
+        syn::Type::Path(path) => &path.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    let args = match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if segment.ident == "Result" => &args.args,
+        _ => return None,
+    };
+    match (args.first(), args.iter().nth(1), args.len()) {
+        (Some(syn::GenericArgument::Type(ok)), Some(syn::GenericArgument::Type(err)), 2) => {
+            Some((ok.clone(), err.clone()))
+        }
+        _ => None,
+    }
+}
+
+#[proc_macro_attribute]
+pub fn tests(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    // we want to re-emit the notated item in all cases
+    let mut tts: TokenStream = item.clone().into();
+
+    // emit as many environment compile errors as possible in one place
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .map_err(|e| {
+            let e = format!("expected $CARGO_MANIFEST_DIR; {}", e);
+            compile_error(&e, Span::call_site())
+        });
+    let args = syn::parse::<AttrArgs>(attr).map_err(|e| e.to_compile_error());
+    let fun = syn::parse::<syn::ItemFn>(item).map_err(|e| e.to_compile_error());
+
+    match (args, fun, manifest_dir) {
+        (Ok(args), Ok(fun), Ok(manifest_dir)) => tts.extend(build_tests(args, fun, manifest_dir)),
+        (Err(a), Err(b), Err(c)) => tts.extend(vec![a, b, c]),
+        (Err(a), Err(b), _) | (Err(a), _, Err(b)) | (_, Err(a), Err(b)) => tts.extend(vec![a, b]),
+        (Err(a), _, _) | (_, Err(a), _) | (_, _, Err(a)) => tts.extend(vec![a]),
+    }
+
+    tts.into()
+}
+
+fn build_tests(args: AttrArgs, fun: syn::ItemFn, manifest_dir: PathBuf) -> TokenStream {
+    let AttrArgs {
+        mode,
+        codec,
+        value,
+        file,
+        section,
+    } = args;
+    let fn_name = &fun.sig.ident;
+    let tested_type = match &fun.sig.output {
+        syn::ReturnType::Type(_, r#type) => (**r#type).clone(),
+        syn::ReturnType::Default => syn::parse_str("()").unwrap(),
+    };
+
+    let tests_path = manifest_dir.join(file.value());
+    let section = section.map(|section| section.to_string());
+    let tests = match read_tests(&tests_path, &file.value(), section.as_deref(), file.span()) {
+        Ok(it) => it,
+        Err(e) => return e,
+    };
+
+    let has_outcome = |outcome| tests.iter().any(|test| test.section.outcome == outcome);
+    let has_err = has_outcome(Outcome::Err);
+    let has_err_message = has_outcome(Outcome::ErrMessage);
+    let (run, ok_type, err_type) = match result_types(&tested_type) {
+        Some((ok_type, err_type)) => {
+            let ser_ok = codec.ser(quote!(&value));
+            let ser_err = codec.ser(quote!(&e));
+            // Only require what the tests use of the error type.
+            let err_arms = match (has_err, has_err_message) {
+                (true, true) => quote! {
+                    Err(e) if outcome == ::conformance::Outcome::Err => {
+                        (::conformance::Outcome::Err, #ser_err?)
+                    }
+                    Err(e) => (::conformance::Outcome::ErrMessage, e.to_string()),
+                },
+                (true, false) => quote! {
+                    Err(e) => (::conformance::Outcome::Err, #ser_err?),
+                },
+                (false, true) => quote! {
+                    Err(e) => (::conformance::Outcome::ErrMessage, e.to_string()),
+                },
+                (false, false) => quote! {
+                    Err(e) => (::conformance::Outcome::ErrMessage, format!("{:?}", e)),
+                },
+            };
+            let run = quote! {
+                match #fn_name(actual) {
+                    Ok(value) => (::conformance::Outcome::Ok, #ser_ok?),
+                    #err_arms
+                }
+            };
+            (run, ok_type, Some(err_type))
+        }
+        None => {
+            if has_err || has_err_message {
+                return compile_error(
+                    "tests with `Err` output need a function that returns `Result<T, E>`",
+                    file.span(),
+                );
+            }
+            let ser = codec.ser(quote!(&#fn_name(actual)));
+            let run = quote!((::conformance::Outcome::Ok, #ser?));
+            (run, tested_type, None)
+        }
+    };
+    // Functions have no normalization of their own, so they go through the tested types.
+    let (ok_type, err_type) = match codec {
+        Codec::Functions { .. } => (value.clone().or(Some(ok_type)), value.or(err_type)),
+        Codec::Format(_) => (value.clone(), value),
+    };
+    let normalize_ok = codec.normalize(ok_type.as_ref(), quote!(expected));
+    let normalize_err = if has_err {
+        let normalize_err = codec.normalize(err_type.as_ref(), quote!(expected));
+        quote!(::conformance::Outcome::Err => #normalize_err,)
+    } else {
+        quote!()
+    };
+    let normalize = quote! {
+        |expected: &str| -> Result<String, Box<dyn ::std::error::Error>> {
+            Ok(match outcome {
+                ::conformance::Outcome::Ok => #normalize_ok,
+                #normalize_err
+                _ => expected.to_string(),
+            })
+        }
+    };
+    let superset_de = codec.de(
+        &syn::parse_quote!(::conformance::superset::Value),
+        quote!(s),
+    );
+    let superset_ser = codec.ser(quote!(value));
+
+    let filepath = tests_path.to_string_lossy().to_string();
+    let display_path = file.value();
+    let mut filename = tests_path
+        .file_stem()
+        .unwrap()
+        .to_string_lossy()
+        .replace('.', "_");
+    if let Some(section) = &section {
+        filename = format!("{}_{}", filename, section);
+    }
+    let section = match section {
+        Some(section) => quote!(Some(#section)),
+        None => quote!(None),
+    };
+    let testing_fn = syn::Ident::new(&filename, Span::call_site());
+
+    let mut tts = match mode {
+        Mode::Exact => quote! {
+            fn #testing_fn(
+                name: &str,
+                line: usize,
+                bless: bool,
+                outcome: ::conformance::Outcome,
+                expected: &str,
+                actual: &str,
+            ) -> Result<(), Box<dyn ::std::error::Error>> {
+                const _: &str = include_str!(#filepath);
+                let (actual_outcome, actual) = #run;
+                let normalize = #normalize;
+                if bless && ::std::env::var_os(::conformance::BLESS_VAR).is_some() {
+                    let normalized = normalize(expected).ok();
+                    if actual_outcome != outcome || normalized.as_ref() != Some(&actual) {
+                        ::conformance::bless(#filepath, name, #section, actual_outcome, &actual)?;
+                    }
+                    return Ok(());
+                }
+                ::conformance::assert_outcome(#display_path, line, outcome, actual_outcome, &actual);
+                let expected = normalize(expected)?;
+                ::conformance::assert_output(#display_path, line, &expected, &actual);
+                Ok(())
+            }
+        },
+        // Partial expectations cannot be blessed, as that would replace them entirely.
+        // Error messages are not structured, so they are always compared exactly.
+        Mode::Superset => quote! {
+            fn #testing_fn(
+                _name: &str,
+                line: usize,
+                _bless: bool,
+                outcome: ::conformance::Outcome,
+                expected: &str,
+                actual: &str,
+            ) -> Result<(), Box<dyn ::std::error::Error>> {
+                const _: &str = include_str!(#filepath);
+                let (actual_outcome, actual) = #run;
+                ::conformance::assert_outcome(#display_path, line, outcome, actual_outcome, &actual);
+                if outcome == ::conformance::Outcome::ErrMessage {
+                    ::conformance::assert_output(#display_path, line, expected, &actual);
+                } else {
+                    ::conformance::assert_contains(
+                        #display_path,
+                        line,
+                        expected,
+                        &actual,
+                        |s: &str| #superset_de,
+                        |value: &::conformance::superset::Value| #superset_ser,
+                    )?;
+                }
+                Ok(())
+            }
+        },
+    };
+    let only = tests.iter().any(|test| test.test.directives.only);
+    for test in tests {
+        let Test {
+            ident,
+            test,
+            section,
+        } = test;
+        let conformance_file::Test {
+            name,
+            line,
+            directives,
+            input,
+            ..
+        } = test;
+        let conformance_file::Section {
+            outcome, output, ..
+        } = section;
+        let tags: String = directives
+            .tags
+            .iter()
+            .map(|tag| format!("_{}", tag))
+            .collect();
+        let test_name = quote::format_ident!("{}{}{}", filename, tags, ident);
+        let outcome = outcome_tokens(outcome);
+
+        let mut attrs = TokenStream::new();
+        match directives.ignore {
+            Some(Some(reason)) => attrs.extend(quote!(#[ignore = #reason])),
+            Some(None) => attrs.extend(quote!(#[ignore])),
+            None if only && !directives.only => {
+                attrs.extend(quote!(#[ignore = "another test in the file is `@only`"]))
+            }
+            None => {}
+        }
+        // Tests that should panic cannot return a `Result`, so they panic on errors as well.
+        // Their output is not blessed, as it is not expected to match.
+        tts.extend(match directives.should_panic {
+            Some(expected) => {
+                let expected = expected.map(|expected| quote!((expected = #expected)));
+                quote! {
+                    #[test]
+                    #attrs
+                    #[should_panic #expected]
+                    fn #test_name() {
+                        #testing_fn(#name, #line, false, #outcome, #output, #input).unwrap()
+                    }
+                }
+            }
+            None => quote! {
+                #[test]
+                #attrs
+                fn #test_name() -> Result<(), Box<dyn ::std::error::Error>> {
+                    #testing_fn(#name, #line, true, #outcome, #output, #input)
+                }
+            },
+        })
+    }
+
+    tts
+}
@@ -0,0 +1,103 @@
+use {
+    crate::{Diagnostic, Severity, SourceFile},
+    tinyc_grammar::TextRange,
+};
+
+/// A `Diagnostic` with its ranges resolved to lines and columns, for serializing.
+///
+/// Lines and columns are one-based, and columns count UTF-8 bytes,
+/// as expected by most CI annotation formats.
+#[derive(serde::Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct JsonDiagnostic<'a> {
+    pub severity: Severity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+    pub message: &'a str,
+    pub file: &'a str,
+    pub labels: Vec<JsonLabel<'a>>,
+    pub notes: &'a [String],
+    pub help: &'a [String],
+}
+
+/// A `Label` with its range resolved to lines and columns.
+#[derive(serde::Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct JsonLabel<'a> {
+    pub message: &'a str,
+    pub primary: bool,
+    /// The byte range of the label.
+    pub range: TextRange,
+    pub start: JsonPosition,
+    pub end: JsonPosition,
+}
+
+/// A one-based line and column.
+#[derive(serde::Serialize)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct JsonPosition {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl<'a> JsonDiagnostic<'a> {
+    pub fn new(diagnostic: &'a Diagnostic, source: &'a SourceFile<'a>) -> Self {
+        let position = |offset: tinyc_grammar::TextSize| {
+            let pos = source.line_index.line_col(offset.into());
+            JsonPosition {
+                line: pos.line + 1,
+                column: pos.col + 1,
+            }
+        };
+        JsonDiagnostic {
+            severity: diagnostic.severity,
+            code: diagnostic.code,
+            message: &diagnostic.message,
+            file: source.name,
+            labels: diagnostic
+                .labels
+                .iter()
+                .map(|label| JsonLabel {
+                    message: &label.message,
+                    primary: label.primary,
+                    range: label.range,
+                    start: position(label.range.start()),
+                    end: position(label.range.end()),
+                })
+                .collect(),
+            notes: &diagnostic.notes,
+            help: &diagnostic.help,
+        }
+    }
+
+    /// Serialize as a single line of JSON.
+    pub fn to_json(&self) -> String {
+        json::to_string(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_diagnostics, JsonDiagnostic, SourceFile};
+
+    #[test]
+    fn json() {
+        let text = "a=1;\nb=;";
+        let source = SourceFile::new("test.c", text);
+        let diagnostics = parse_diagnostics(&tinyc_parser::parse_text(text));
+        let json: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| JsonDiagnostic::new(diagnostic, &source).to_json())
+            .collect();
+        assert_eq!(
+            json,
+            [concat!(
+                r#"{"severity":"error","code":"E0002","#,
+                r#""message":"expected an expression, found `;`","file":"test.c","#,
+                r#""labels":[{"message":"unexpected `;`","primary":true,"range":[7,8],"#,
+                r#""start":{"line":2,"column":3},"end":{"line":2,"column":4}}],"#,
+                r#""notes":[],"help":[]}"#,
+            )],
+        );
+    }
+}
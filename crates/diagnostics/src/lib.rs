@@ -0,0 +1,195 @@
+//! Presentation of errors in Tiny-C programs.
+//!
+//! A `Diagnostic` is a message about a program along with labeled ranges of its source.
+//! It can be rendered for humans by a `Renderer`, either as plain text
+//! or colored for a terminal, or serialized as JSON for other tools.
+
+pub use crate::{
+    json::JsonDiagnostic,
+    render::{Renderer, SourceFile},
+};
+
+use {
+    tinyc_grammar::{SyntaxKind, TextRange, TokenKind},
+    tinyc_parser::{ErrorCode, Parse, SyntaxError},
+};
+
+mod json;
+mod render;
+
+/// How severe a `Diagnostic` is.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A message about a program.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// A short stable identifier for this kind of diagnostic, if it has one.
+    pub code: Option<&'static str>,
+    pub message: String,
+    /// The source ranges this diagnostic is about. The first label is the primary one.
+    pub labels: Vec<Label>,
+    /// Additional context, shown after the source.
+    pub notes: Vec<String>,
+    /// Suggestions for fixing the problem, shown after the notes.
+    pub help: Vec<String>,
+}
+
+/// A range of source text with a message about it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Label {
+    pub range: TextRange,
+    pub message: String,
+    /// Whether this is where the problem is, rather than related context.
+    pub primary: bool,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity,
+            code: None,
+            message: message.into(),
+            labels: vec![],
+            notes: vec![],
+            help: vec![],
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Diagnostic::new(Severity::Error, message)
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_label(mut self, label: Label) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    /// The range of the primary label, if there is one.
+    pub fn range(&self) -> Option<TextRange> {
+        self.labels
+            .iter()
+            .find(|label| label.primary)
+            .map(|label| label.range)
+    }
+}
+
+impl Label {
+    pub fn primary(range: TextRange, message: impl Into<String>) -> Self {
+        Label {
+            range,
+            message: message.into(),
+            primary: true,
+        }
+    }
+
+    pub fn secondary(range: TextRange, message: impl Into<String>) -> Self {
+        Label {
+            range,
+            message: message.into(),
+            primary: false,
+        }
+    }
+}
+
+/// Is this token an error from the lexer, which gets its own diagnostic?
+fn is_lexer_error(kind: Option<TokenKind>) -> bool {
+    matches!(
+        kind,
+        Some(TokenKind::ERROR) | Some(TokenKind::UnterminatedBlockComment)
+    )
+}
+
+impl From<&SyntaxError> for Diagnostic {
+    fn from(error: &SyntaxError) -> Self {
+        let label = match error.error.found {
+            Some(kind) => format!("unexpected {}", kind.description()),
+            None => "unexpected end of input".to_string(),
+        };
+        let mut diagnostic = Diagnostic::error(error.to_string())
+            .with_code(error.error.code.as_str())
+            .with_label(Label::primary(error.range, label));
+        match error.error.code {
+            ErrorCode::ExpectedToken => {
+                let expected: Vec<_> = error.error.expected.iter().collect();
+                if expected == [TokenKind::LeftParenthesis] {
+                    diagnostic = diagnostic
+                        .with_help("the conditions of `if` and `while` must be in parentheses");
+                }
+            }
+            ErrorCode::ExpectedStatement => {
+                if error.error.found == Some(TokenKind::RightCurlyBracket) {
+                    diagnostic = diagnostic.with_note("this `}` does not close any block");
+                }
+            }
+            ErrorCode::NestingTooDeep => {
+                diagnostic = diagnostic.with_note("the rest of this nesting is not parsed");
+            }
+            ErrorCode::ExpectedExpression => {}
+        }
+        diagnostic
+    }
+}
+
+/// All diagnostics for a parsed program: lexical errors and syntax errors, in source order.
+///
+/// Syntax errors at a token the lexer already complained about are left out.
+pub fn parse_diagnostics(parse: &Parse) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = parse
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter_map(|token| match token.kind() {
+            SyntaxKind::ERROR => {
+                let c = token.text().chars().next().unwrap_or_default();
+                let mut diagnostic = Diagnostic::error(format!("unexpected character `{}`", c))
+                    .with_label(Label::primary(token.text_range(), "not valid in Tiny-C"));
+                if c.is_ascii_uppercase() {
+                    diagnostic = diagnostic
+                        .with_help("variable names are the lowercase letters `a` through `z`");
+                }
+                Some(diagnostic)
+            }
+            SyntaxKind::UnterminatedBlockComment => {
+                let start = token.text_range().start();
+                let opener = TextRange::at(start, 2.into());
+                Some(
+                    Diagnostic::error("unterminated block comment")
+                        .with_label(Label::primary(opener, "comment starts here"))
+                        .with_help("add `*/` to close the comment"),
+                )
+            }
+            _ => None,
+        })
+        .collect();
+    diagnostics.extend(
+        parse
+            .errors()
+            .iter()
+            .filter(|error| !is_lexer_error(error.error.found))
+            .map(Diagnostic::from),
+    );
+    diagnostics.sort_by_key(|diagnostic| diagnostic.range().map(|range| range.start()));
+    diagnostics
+}
@@ -0,0 +1,282 @@
+use {
+    crate::{Diagnostic, Label, Severity},
+    std::fmt::{self, Write},
+    tinyc_lexer::LineIndex,
+};
+
+/// A named source text that diagnostics refer to.
+#[derive(Debug, Clone)]
+pub struct SourceFile<'a> {
+    /// The name to show for the source, usually its path.
+    pub name: &'a str,
+    pub text: &'a str,
+    pub line_index: LineIndex,
+}
+
+impl<'a> SourceFile<'a> {
+    pub fn new(name: &'a str, text: &'a str) -> Self {
+        SourceFile {
+            name,
+            text,
+            line_index: LineIndex::new(text),
+        }
+    }
+}
+
+/// Renders diagnostics for humans, in the style of `rustc`:
+///
+/// ```text
+/// error[E0002]: expected an expression, found `;`
+///  --> example.c:2:3
+///   |
+/// 2 | b=;
+///   |   ^ unexpected `;`
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Renderer {
+    color: bool,
+}
+
+/// ANSI escape codes.
+mod style {
+    pub const RESET: &str = "\x1b[0m";
+    pub const BOLD: &str = "\x1b[1m";
+    pub const RED: &str = "\x1b[1;31m";
+    pub const YELLOW: &str = "\x1b[1;33m";
+    pub const BLUE: &str = "\x1b[1;34m";
+}
+
+impl Renderer {
+    /// A renderer producing plain text.
+    pub fn plain() -> Self {
+        Renderer { color: false }
+    }
+
+    /// A renderer producing text colored with ANSI escape codes.
+    pub fn colored() -> Self {
+        Renderer { color: true }
+    }
+
+    /// Render `diagnostic` to a string, ending with a newline.
+    pub fn render(&self, diagnostic: &Diagnostic, source: &SourceFile<'_>) -> String {
+        let mut out = String::new();
+        self.write(&mut out, diagnostic, source).unwrap();
+        out
+    }
+
+    /// Wrap `text` in the given style, if coloring.
+    fn paint<'a>(&self, style: &'static str, text: &'a str) -> Painted<'a> {
+        Painted {
+            style: if self.color { Some(style) } else { None },
+            text,
+        }
+    }
+
+    fn severity_style(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => style::RED,
+            Severity::Warning => style::YELLOW,
+        }
+    }
+
+    /// Write the rendering of `diagnostic`, ending with a newline.
+    pub fn write(
+        &self,
+        out: &mut dyn Write,
+        diagnostic: &Diagnostic,
+        source: &SourceFile<'_>,
+    ) -> fmt::Result {
+        let severity_style = Self::severity_style(diagnostic.severity);
+        let severity = match diagnostic.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let header = match diagnostic.code {
+            Some(code) => format!("{}[{}]", severity, code),
+            None => severity.to_string(),
+        };
+        writeln!(
+            out,
+            "{}{}",
+            self.paint(severity_style, &header),
+            self.paint(style::BOLD, &format!(": {}", diagnostic.message)),
+        )?;
+
+        let index = &source.line_index;
+        let mut labels: Vec<_> = diagnostic
+            .labels
+            .iter()
+            .map(|label| (index.line_col(label.range.start().into()), label))
+            .collect();
+        labels.sort_by_key(|&(pos, _)| pos);
+        let last_line = labels.iter().map(|(pos, _)| pos.line + 1).max();
+        let width = last_line.map_or(0, |line| line.to_string().len());
+
+        let primary = labels.iter().find(|(_, label)| label.primary);
+        if let Some(&(pos, _)) = primary.or_else(|| labels.first()) {
+            writeln!(
+                out,
+                "{}{} {}:{}",
+                " ".repeat(width),
+                self.paint(style::BLUE, "-->"),
+                source.name,
+                index.to_chars(pos),
+            )?;
+            self.gutter(out, width, None)?;
+            writeln!(out)?;
+        }
+
+        let mut previous_line = None;
+        for &(pos, label) in &labels {
+            if previous_line != Some(pos.line) {
+                if previous_line.is_some_and(|line| line + 1 < pos.line) {
+                    writeln!(out, "{}", self.paint(style::BLUE, "..."))?;
+                }
+                self.gutter(out, width, Some(pos.line))?;
+                let line = line_text(source, pos.line);
+                if line.is_empty() {
+                    writeln!(out)?;
+                } else {
+                    writeln!(out, " {}", line)?;
+                }
+                previous_line = Some(pos.line);
+            }
+            let marker_style = if label.primary {
+                severity_style
+            } else {
+                style::BLUE
+            };
+            self.write_underline(out, source, width, pos.line, label, marker_style)?;
+        }
+
+        if !(diagnostic.notes.is_empty() && diagnostic.help.is_empty()) {
+            if !labels.is_empty() {
+                self.gutter(out, width, None)?;
+                writeln!(out)?;
+            }
+            let notes = diagnostic.notes.iter().map(|note| ("note:", note));
+            let help = diagnostic.help.iter().map(|help| ("help:", help));
+            for (kind, text) in notes.chain(help) {
+                writeln!(
+                    out,
+                    "{}{} {} {}",
+                    " ".repeat(width + 1),
+                    self.paint(style::BLUE, "="),
+                    self.paint(style::BOLD, kind),
+                    text,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the gutter, with a line number if given.
+    fn gutter(&self, out: &mut dyn Write, width: usize, line: Option<u32>) -> fmt::Result {
+        let number = line.map_or(String::new(), |line| (line + 1).to_string());
+        let gutter = format!("{:>w$} |", number, w = width);
+        write!(out, "{}", self.paint(style::BLUE, &gutter))
+    }
+
+    /// Underline the part of `label` on `line`, which it starts on.
+    fn write_underline(
+        &self,
+        out: &mut dyn Write,
+        source: &SourceFile<'_>,
+        width: usize,
+        line: u32,
+        label: &Label,
+        marker_style: &'static str,
+    ) -> fmt::Result {
+        let line_start = source.line_index.line_range(line).unwrap().start;
+        let text = line_text(source, line);
+        let start = (u32::from(label.range.start()) - line_start) as usize;
+        let start = start.min(text.len());
+        let end = (u32::from(label.range.end()) - line_start) as usize;
+        let end = end.min(text.len()).max(start);
+
+        // keep tabs so that the markers line up with the source line
+        let indent: String = text[..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let marker = if label.primary { "^" } else { "-" };
+        let markers = marker.repeat(text[start..end].chars().count().max(1));
+
+        self.gutter(out, width, None)?;
+        write!(out, " {}{}", indent, self.paint(marker_style, &markers))?;
+        if !label.message.is_empty() {
+            write!(out, " {}", self.paint(marker_style, &label.message))?;
+        }
+        writeln!(out)
+    }
+}
+
+/// The content of `line`, without its terminator.
+fn line_text<'a>(source: &SourceFile<'a>, line: u32) -> &'a str {
+    let range = source.line_index.line_range(line).unwrap();
+    &source.text[range.start as usize..range.end as usize]
+}
+
+struct Painted<'a> {
+    style: Option<&'static str>,
+    text: &'a str,
+}
+
+impl fmt::Display for Painted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.style {
+            Some(style) => write!(f, "{}{}{}", style, self.text, style::RESET),
+            None => f.write_str(self.text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        tinyc_grammar::{TextRange, TextSize},
+    };
+
+    fn range(start: u32, end: u32) -> TextRange {
+        TextRange::new(TextSize::from(start), TextSize::from(end))
+    }
+
+    #[test]
+    fn labels() {
+        let text = "i=1;\nwhile (i<100)\n\n\n  i=i+j;\n";
+        let source = SourceFile::new("labels.c", text);
+        let diagnostic = Diagnostic::error("something is wrong")
+            .with_label(Label::primary(range(27, 28), "this"))
+            .with_label(Label::secondary(range(23, 24), "that"))
+            .with_label(Label::secondary(range(5, 18), "because of this"))
+            .with_help("fix it");
+        assert_eq!(
+            Renderer::plain().render(&diagnostic, &source),
+            "\
+error: something is wrong
+ --> labels.c:5:7
+  |
+2 | while (i<100)
+  | ------------- because of this
+...
+5 |   i=i+j;
+  |   - that
+  |       ^ this
+  |
+  = help: fix it
+",
+        );
+    }
+
+    #[test]
+    fn colored() {
+        let source = SourceFile::new("colored.c", "a=;");
+        let diagnostic = Diagnostic::error("oops")
+            .with_code("E0000")
+            .with_label(Label::primary(range(2, 3), ""));
+        let rendered = Renderer::colored().render(&diagnostic, &source);
+        assert!(rendered.starts_with("\x1b[1;31merror[E0000]\x1b[0m\x1b[1m: oops\x1b[0m\n"));
+        assert!(rendered.contains("\x1b[1;31m^\x1b[0m"));
+    }
+}
@@ -0,0 +1,31 @@
+use {
+    std::{convert::Infallible, fmt, str::FromStr},
+    tinyc_diagnostics::{parse_diagnostics, Renderer, SourceFile},
+};
+
+/// Rendered diagnostics, compared as plain text.
+struct Rendered(String);
+
+impl fmt::Display for Rendered {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Rendered {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Rendered(s.to_string()))
+    }
+}
+
+#[conformance::tests(exact, format=text, file="tests/plain.test")]
+fn render_plain(s: &str) -> Rendered {
+    let source = SourceFile::new("test.c", s);
+    let rendered: Vec<_> = parse_diagnostics(&tinyc_parser::parse_text(s))
+        .iter()
+        .map(|diagnostic| Renderer::plain().render(diagnostic, &source))
+        .collect();
+    Rendered(rendered.join("\n").trim_end().to_string())
+}
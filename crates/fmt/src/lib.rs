@@ -0,0 +1,225 @@
+//! A formatter that reprints Tiny-C programs in a canonical style.
+//!
+//! - one statement per line, and blocks indented by four spaces;
+//! - the body of an `if` or `while` on its own line and indented, unless it is a block,
+//!   in which case the `{` ends the line of the condition;
+//! - `else` after the `}` of a block on the same line, otherwise on its own line,
+//!   and `else if` kept together;
+//! - one space around `=`, `<`, `+` and `-`, and none inside parentheses.
+//!
+//! Comments are kept. A comment that followed code on the same line still does;
+//! any other comment gets a line of its own. Runs of blank lines between lines
+//! are collapsed to one, and every other bit of whitespace is replaced.
+//!
+//! Formatting is idempotent: formatting formatted code does not change it.
+
+pub use tinyc_parser::SyntaxError;
+
+use tinyc_grammar::{
+    ast::{self, AstNode},
+    syntax::{SyntaxNode, SyntaxToken},
+    SyntaxKind, TokenKind,
+};
+
+const INDENT: &str = "    ";
+
+/// Format the source of a program.
+///
+/// Programs with syntax errors are not formatted; their errors are returned instead.
+pub fn format_text(text: &str) -> Result<String, Vec<SyntaxError>> {
+    let parse = tinyc_parser::parse_text(text);
+    if parse.errors().is_empty() {
+        Ok(format(&parse.syntax()))
+    } else {
+        Err(parse.errors().to_vec())
+    }
+}
+
+/// Format a syntax tree.
+///
+/// Trees with errors are formatted as well as is possible,
+/// but the result is not guaranteed to be canonical.
+pub fn format(program: &SyntaxNode) -> String {
+    let mut out = String::new();
+    let mut prev: Option<SyntaxToken> = None;
+    // The number of line breaks in the whitespace since `prev`.
+    let mut newlines = 0;
+    for token in program
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+    {
+        if token.kind() == SyntaxKind::Whitespace {
+            newlines += token.text().matches('\n').count();
+            continue;
+        }
+        if let Some(prev) = &prev {
+            match separator(prev, &token, newlines) {
+                Separator::None => {}
+                Separator::Space => out.push(' '),
+                Separator::Line => {
+                    let blank_line = newlines > 1
+                        && prev.kind() != SyntaxKind::LeftCurlyBracket
+                        && token.kind() != SyntaxKind::RightCurlyBracket;
+                    if blank_line {
+                        out.push('\n');
+                    }
+                    out.push('\n');
+                    for _ in 0..indent(&token) {
+                        out.push_str(INDENT);
+                    }
+                }
+            }
+        }
+        match token.kind() {
+            SyntaxKind::LineComment => out.push_str(token.text().trim_end()),
+            _ => out.push_str(token.text()),
+        }
+        prev = Some(token);
+        newlines = 0;
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// What goes between two tokens.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Separator {
+    None,
+    Space,
+    Line,
+}
+
+fn is_trivia(token: &SyntaxToken) -> bool {
+    token.kind().as_token().is_some_and(TokenKind::is_trivia)
+}
+
+fn is_comment(token: &SyntaxToken) -> bool {
+    matches!(
+        token.kind(),
+        SyntaxKind::LineComment | SyntaxKind::BlockComment
+    )
+}
+
+/// The separator between two adjacent non-whitespace tokens,
+/// which originally had `newlines` line breaks between them.
+fn separator(prev: &SyntaxToken, next: &SyntaxToken, newlines: usize) -> Separator {
+    if is_comment(next) {
+        return if newlines > 0 {
+            Separator::Line
+        } else {
+            Separator::Space
+        };
+    }
+    match prev.kind() {
+        SyntaxKind::LineComment => Separator::Line,
+        SyntaxKind::BlockComment if newlines > 0 => Separator::Line,
+        // A block comment within a line is spaced like the code before it.
+        SyntaxKind::BlockComment => {
+            let code = std::iter::successors(prev.prev_token(), |token| token.prev_token())
+                .find(|token| !is_trivia(token));
+            match code {
+                Some(code) => code_separator(&code, next),
+                None => Separator::Space,
+            }
+        }
+        _ => code_separator(prev, next),
+    }
+}
+
+/// The separator between two adjacent tokens that are not trivia.
+fn code_separator(prev: &SyntaxToken, next: &SyntaxToken) -> Separator {
+    use SyntaxKind::*;
+    if (prev.kind(), next.kind()) == (LeftCurlyBracket, RightCurlyBracket) {
+        return Separator::None;
+    }
+    if let Some(statement) = statement_starting_at(next) {
+        return if continues_line(&statement) {
+            Separator::Space
+        } else {
+            Separator::Line
+        };
+    }
+    match (prev.kind(), next.kind()) {
+        (LeftCurlyBracket, _) | (_, RightCurlyBracket) => Separator::Line,
+        (_, Semicolon) | (LeftParenthesis, _) | (_, RightParenthesis) => Separator::None,
+        (RightCurlyBracket, Else) => Separator::Space,
+        (_, Else) => Separator::Line,
+        _ => Separator::Space,
+    }
+}
+
+/// The statement whose first non-trivia token is `token`, if there is one.
+fn statement_starting_at(token: &SyntaxToken) -> Option<SyntaxNode> {
+    let statement = token
+        .parent_ancestors()
+        .find(|node| ast::Statement::can_cast(node.kind()))?;
+    let first = statement
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .find(|token| !is_trivia(token));
+    if first.as_ref() == Some(token) {
+        Some(statement)
+    } else {
+        None
+    }
+}
+
+/// Is this statement the body of an `if` or `while`?
+fn is_body(statement: &SyntaxNode) -> bool {
+    statement.parent().is_some_and(|parent| {
+        matches!(
+            parent.kind(),
+            SyntaxKind::StatementIf | SyntaxKind::StatementWhile
+        )
+    })
+}
+
+/// Is this statement the `if` of an `else if`?
+fn is_else_if(statement: &SyntaxNode) -> bool {
+    statement.kind() == SyntaxKind::StatementIf
+        && statement
+            .parent()
+            .and_then(ast::StatementIf::cast)
+            .and_then(|parent| parent.else_branch())
+            .is_some_and(|branch| branch.syntax() == statement)
+}
+
+/// Does this statement start on the line of the code before it?
+fn continues_line(statement: &SyntaxNode) -> bool {
+    (is_body(statement) && statement.kind() == SyntaxKind::StatementBlock) || is_else_if(statement)
+}
+
+/// The indentation level of a token that starts a line.
+fn indent(token: &SyntaxToken) -> usize {
+    if is_comment(token) {
+        // Comments on their own line are indented like the code after them.
+        let next = std::iter::successors(token.next_token(), |token| token.next_token())
+            .find(|token| !is_trivia(token));
+        return match next {
+            // ...inside of the block, for a comment at its end.
+            Some(next) if next.kind() == SyntaxKind::RightCurlyBracket => indent(&next) + 1,
+            Some(next) => indent(&next),
+            None => 0,
+        };
+    }
+    let mut level = 0;
+    let mut child: Option<SyntaxNode> = None;
+    for node in token.parent_ancestors() {
+        if let Some(child) = &child {
+            let nested = match node.kind() {
+                SyntaxKind::StatementBlock => true,
+                SyntaxKind::StatementIf | SyntaxKind::StatementWhile => {
+                    ast::Statement::can_cast(child.kind()) && !continues_line(child)
+                }
+                _ => false,
+            };
+            if nested {
+                level += 1;
+            }
+        }
+        child = Some(node);
+    }
+    level
+}
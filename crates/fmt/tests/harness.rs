@@ -0,0 +1,9 @@
+use tinyc_fmt::format_text;
+
+// Also checks that formatting the formatted code does not change it.
+#[conformance::tests(exact, format=text, file="tests/format.test")]
+fn fmt(s: &str) -> String {
+    let formatted = format_text(s).unwrap();
+    assert_eq!(format_text(&formatted).unwrap(), formatted);
+    formatted.trim_end().to_string()
+}
@@ -0,0 +1,141 @@
+//! Property tests that formatting valid programs is idempotent
+//! and changes nothing but whitespace.
+
+use {
+    proptest::{
+        collection::vec,
+        prelude::*,
+        sample::{select, Index},
+    },
+    tinyc_fmt::format_text,
+    tinyc_lexer::{tokenize, TokenKind},
+};
+
+/// Whitespace and comments to put between tokens.
+const SEPARATORS: &[&str] = &[
+    " ",
+    "  ",
+    "\n",
+    "\n\n\n",
+    "\t",
+    "\r\n",
+    " // c\n",
+    "/* c */",
+    "\n/* c\n */\n",
+];
+
+fn join(parts: Vec<Vec<String>>) -> Vec<String> {
+    parts.concat()
+}
+
+fn expression() -> impl Strategy<Value = Vec<String>> {
+    let term = prop_oneof![
+        "[a-e]".prop_map(|id| vec![id]),
+        (0..1000u32).prop_map(|int| vec![int.to_string()]),
+    ];
+    term.prop_recursive(4, 32, 3, |expr| {
+        prop_oneof![
+            (expr.clone(), select(&["<", "+", "-"][..]), expr.clone())
+                .prop_map(|(lhs, op, rhs)| join(vec![lhs, vec![op.into()], rhs])),
+            expr.clone()
+                .prop_map(|expr| join(vec![vec!["(".into()], expr, vec![")".into()]])),
+            ("[a-e]", expr).prop_map(|(id, expr)| join(vec![
+                vec!["(".into(), id, "=".into()],
+                expr,
+                vec![")".into()],
+            ])),
+        ]
+    })
+}
+
+fn condition() -> impl Strategy<Value = Vec<String>> {
+    expression().prop_map(|expr| join(vec![vec!["(".into()], expr, vec![")".into()]]))
+}
+
+fn statement() -> impl Strategy<Value = Vec<String>> {
+    let simple = prop_oneof![
+        ("[a-e]", expression()).prop_map(|(id, expr)| join(vec![
+            vec![id, "=".into()],
+            expr,
+            vec![";".into()],
+        ])),
+        expression().prop_map(|expr| join(vec![expr, vec![";".into()]])),
+        Just(vec![";".to_string()]),
+    ];
+    simple.prop_recursive(4, 32, 4, |stmt| {
+        prop_oneof![
+            (condition(), stmt.clone()).prop_map(|(cond, then)| join(vec![
+                vec!["if".into()],
+                cond,
+                then
+            ])),
+            (condition(), stmt.clone(), stmt.clone()).prop_map(|(cond, then, otherwise)| join(
+                vec![
+                    vec!["if".into()],
+                    cond,
+                    then,
+                    vec!["else".into()],
+                    otherwise
+                ]
+            )),
+            (condition(), stmt.clone()).prop_map(|(cond, body)| join(vec![
+                vec!["while".into()],
+                cond,
+                body
+            ])),
+            vec(stmt, 0..4).prop_map(|stmts| join(vec![
+                vec!["{".into()],
+                stmts.concat(),
+                vec!["}".into()],
+            ])),
+        ]
+    })
+}
+
+/// A valid program, with arbitrary whitespace and comments between its tokens.
+fn program() -> impl Strategy<Value = String> {
+    (
+        vec(statement(), 0..6),
+        vec(select(SEPARATORS), 0..256),
+        any::<Index>(),
+    )
+        .prop_map(|(stmts, separators, default)| {
+            let default = default.get(SEPARATORS);
+            let mut text = String::new();
+            for (i, token) in stmts.concat().iter().enumerate() {
+                text.push_str(token);
+                text.push_str(separators.get(i).unwrap_or(default));
+            }
+            text
+        })
+}
+
+/// The text of every token that is not whitespace.
+fn tokens(text: &str) -> Vec<&str> {
+    let mut offset = 0;
+    let mut tokens = vec![];
+    for token in tokenize(text) {
+        let token_text = &text[offset..offset + token.len as usize];
+        offset += token.len as usize;
+        match token.kind {
+            TokenKind::Whitespace => {}
+            TokenKind::LineComment => tokens.push(token_text.trim_end()),
+            _ => tokens.push(token_text),
+        }
+    }
+    tokens
+}
+
+proptest! {
+    #[test]
+    fn format_is_idempotent(text in program()) {
+        let formatted = format_text(&text).unwrap();
+        prop_assert_eq!(format_text(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn format_only_changes_whitespace(text in program()) {
+        let formatted = format_text(&text).unwrap();
+        prop_assert_eq!(tokens(&formatted), tokens(&text));
+    }
+}
@@ -3,14 +3,13 @@ use {
     heck::*,
     serde::{Deserialize, Serialize},
     std::{
-        collections::HashMap,
+        collections::{BTreeMap, HashMap},
         env,
         error::Error,
         fs,
         path::{Path, PathBuf},
     },
     tera::{self, Context, Tera, Value},
-    toml,
 };
 
 const MANIFEST: &str = env!("CARGO_MANIFEST_DIR");
@@ -19,6 +18,7 @@ const TEMPLATE_DIR: &str = "templates";
 
 pub const SYNTAX_KINDS: &str = "syntax_kinds.rs";
 pub const TOKEN_KINDS: &str = "token_kinds.rs";
+pub const AST: &str = "ast.rs";
 
 fn project_root() -> &'static Path {
     Path::new(MANIFEST).ancestors().nth(2).unwrap()
@@ -30,10 +30,15 @@ struct SyntaxConfig {
     literals: Vec<String>,
     punctuation: Vec<PunctuationConfig>,
     tokens: Vec<String>,
+    trivia: Vec<String>,
     nonterminals: Vec<String>,
+    token_sets: Vec<TokenSetConfig>,
     // calculated
     terminals: Vec<String>,
     all_kinds: Vec<String>,
+    binary_operators: Vec<BinaryOperatorConfig>,
+    nodes: Vec<NodeConfig>,
+    categories: Vec<CategoryConfig>,
 }
 
 impl<'de> Deserialize<'de> for SyntaxConfig {
@@ -41,6 +46,8 @@ impl<'de> Deserialize<'de> for SyntaxConfig {
     where
         D: serde::de::Deserializer<'de>,
     {
+        use serde::de::Error;
+
         #[derive(Deserialize)]
         #[serde(rename = "SyntaxConfig")]
         struct Helper {
@@ -48,20 +55,32 @@ impl<'de> Deserialize<'de> for SyntaxConfig {
             literals: Vec<String>,
             punctuation: Vec<PunctuationConfig>,
             tokens: Vec<String>,
+            trivia: Vec<String>,
+            precedence: Vec<Vec<(char, String)>>,
             nonterminals: Vec<String>,
+            #[serde(default)]
+            token_sets: BTreeMap<String, Vec<String>>,
+            fields: BTreeMap<String, Vec<FieldHelper>>,
         }
+        #[derive(Deserialize)]
+        struct FieldHelper(String, String, #[serde(default)] Option<String>);
         let Helper {
             keywords,
             literals,
             punctuation,
             tokens,
+            trivia,
+            precedence,
             nonterminals,
+            token_sets,
+            mut fields,
         } = Helper::deserialize(deserializer)?;
         let terminals: Vec<_> = keywords
             .iter()
             .chain(literals.iter())
             .chain(punctuation.iter().map(|punct| &punct.name))
             .chain(tokens.iter())
+            .chain(trivia.iter())
             .cloned()
             .collect();
         let all_kinds: Vec<_> = terminals
@@ -69,14 +88,173 @@ impl<'de> Deserialize<'de> for SyntaxConfig {
             .chain(nonterminals.iter())
             .cloned()
             .collect();
+        let mut binary_operators = vec![];
+        for (level, operators) in precedence.into_iter().enumerate() {
+            for (character, node) in operators {
+                let token = punctuation
+                    .iter()
+                    .find(|punct| punct.character == character)
+                    .ok_or_else(|| {
+                        D::Error::custom(format!("unknown binary operator `{}`", character))
+                    })?
+                    .name
+                    .clone();
+                binary_operators.push(BinaryOperatorConfig {
+                    token,
+                    node,
+                    precedence: level as u8 + 1,
+                });
+            }
+        }
+        let builtin_sets = ["keywords", "literals", "punctuation", "trivia"];
+        // the names of the `TokenSet` constants, which must not clash
+        let mut constants: Vec<String> = builtin_sets
+            .iter()
+            .chain(&["empty"])
+            .map(|name| name.to_shouty_snake_case())
+            .collect();
+        let mut sets: Vec<TokenSetConfig> = vec![];
+        for (name, members) in &token_sets {
+            let constant = name.to_shouty_snake_case();
+            if constants.contains(&constant) {
+                return Err(D::Error::custom(format!(
+                    "token set `{}` has the name of another set, `TokenSet::{}`",
+                    name, constant,
+                )));
+            }
+            constants.push(constant);
+            let mut set = TokenSetConfig {
+                name: name.clone(),
+                kinds: vec![],
+                sets: vec![],
+            };
+            for member in members {
+                if terminals.contains(member) {
+                    set.kinds.push(member.clone());
+                } else if builtin_sets.contains(&&**member) || token_sets.contains_key(member) {
+                    set.sets.push(member.clone());
+                } else {
+                    return Err(D::Error::custom(format!(
+                        "unknown token or token set `{}` in token set `{}`",
+                        member, name,
+                    )));
+                }
+            }
+            sets.push(set);
+        }
+        // a set that includes itself would be a cycle between the constants
+        for set in &sets {
+            let mut included: Vec<&str> = vec![];
+            let mut stack: Vec<&str> = set.sets.iter().map(String::as_str).collect();
+            while let Some(name) = stack.pop() {
+                if name == set.name {
+                    return Err(D::Error::custom(format!(
+                        "token set `{}` includes itself",
+                        name,
+                    )));
+                }
+                if !included.contains(&name) {
+                    included.push(name);
+                    if let Some(other) = sets.iter().find(|it| it.name == name) {
+                        stack.extend(other.sets.iter().map(String::as_str));
+                    }
+                }
+            }
+        }
+        let mut categories: Vec<CategoryConfig> = vec![];
+        for nonterminal in &nonterminals {
+            if let Some((category, variant)) = nonterminal.split_once(' ') {
+                let variant = variant.to_string();
+                match categories.iter_mut().find(|it| it.name == category) {
+                    Some(category) => category.variants.push(variant),
+                    None => categories.push(CategoryConfig {
+                        name: category.to_string(),
+                        variants: vec![variant],
+                    }),
+                }
+            }
+        }
+        let mut nodes = vec![];
+        for nonterminal in &nonterminals {
+            let mut node = NodeConfig {
+                name: nonterminal.clone(),
+                fields: vec![],
+            };
+            let declared = fields.remove(nonterminal).unwrap_or_default();
+            for (i, FieldHelper(name, ty, after)) in declared.iter().enumerate() {
+                let (ty, many) = match ty.strip_suffix('*') {
+                    Some(ty) => (ty.to_string(), true),
+                    None => (ty.clone(), false),
+                };
+                let kind = if terminals.contains(&ty) {
+                    "token"
+                } else if nonterminals.contains(&ty) || categories.iter().any(|it| it.name == ty) {
+                    if many {
+                        "nodes"
+                    } else {
+                        "node"
+                    }
+                } else {
+                    return Err(D::Error::custom(format!(
+                        "unknown type `{}` for field `{}` of `{}`",
+                        ty, name, nonterminal,
+                    )));
+                };
+                if let Some(after) = after {
+                    if !terminals.contains(after) {
+                        return Err(D::Error::custom(format!(
+                            "unknown token `{}` for field `{}` of `{}`",
+                            after, name, nonterminal,
+                        )));
+                    }
+                    if kind != "node" {
+                        return Err(D::Error::custom(format!(
+                            "field `{}` of `{}` follows a token, so it must be a single node",
+                            name, nonterminal,
+                        )));
+                    }
+                }
+                // the field ends where a later field starts after another token
+                let before = declared[i + 1..]
+                    .iter()
+                    .filter_map(|FieldHelper(_, _, it)| it.as_ref())
+                    .find(|it| Some(*it) != after.as_ref())
+                    .cloned();
+                let index = node
+                    .fields
+                    .iter()
+                    .filter(|field| field.ty == ty && field.after == *after)
+                    .count();
+                node.fields.push(FieldConfig {
+                    name: name.clone(),
+                    kind,
+                    ty,
+                    after: after.clone(),
+                    before,
+                    index,
+                });
+            }
+            nodes.push(node);
+        }
+        if let Some(nonterminal) = fields.keys().next() {
+            return Err(D::Error::custom(format!(
+                "fields declared for unknown nonterminal `{}`",
+                nonterminal,
+            )));
+        }
         Ok(SyntaxConfig {
             keywords,
             literals,
             punctuation,
             tokens,
+            trivia,
             nonterminals,
+            token_sets: sets,
             terminals,
             all_kinds,
+            binary_operators,
+            nodes,
+            categories,
         })
     }
 }
@@ -102,6 +280,48 @@ impl<'de> Deserialize<'de> for PunctuationConfig {
     }
 }
 
+#[derive(Serialize)]
+struct TokenSetConfig {
+    name: String,
+    /// The tokens in the set.
+    kinds: Vec<String>,
+    /// The other token sets included in the set.
+    sets: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct BinaryOperatorConfig {
+    token: String,
+    node: String,
+    precedence: u8,
+}
+
+#[derive(Serialize)]
+struct NodeConfig {
+    name: String,
+    fields: Vec<FieldConfig>,
+}
+
+#[derive(Serialize)]
+struct FieldConfig {
+    name: String,
+    /// One of `token`, `node`, or `nodes`.
+    kind: &'static str,
+    ty: String,
+    /// The token that the field follows, if any.
+    after: Option<String>,
+    /// The token of a later field that the field precedes, if any.
+    before: Option<String>,
+    /// The number of preceding fields with the same type that follow the same token.
+    index: usize,
+}
+
+#[derive(Serialize)]
+struct CategoryConfig {
+    name: String,
+    variants: Vec<String>,
+}
+
 fn make_filter_fn<'a, T: Into<Value> + serde::de::DeserializeOwned>(
     name: &'a str,
     f: impl Fn(T) -> T + Sync + Send + 'a,
@@ -134,6 +354,10 @@ fn main() -> Result<(), Box<dyn Error>> {
             "snake_case",
             make_filter_fn("snake_case", |s: String| s.to_snake_case()),
         );
+        tera.register_filter(
+            "shouty_snake_case",
+            make_filter_fn("shouty_snake_case", |s: String| s.to_shouty_snake_case()),
+        );
         tera
     };
 
@@ -142,5 +366,6 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     fs::write(out.join(SYNTAX_KINDS), tera.render(SYNTAX_KINDS, &context)?)?;
     fs::write(out.join(TOKEN_KINDS), tera.render(TOKEN_KINDS, &context)?)?;
+    fs::write(out.join(AST), tera.render(AST, &context)?)?;
     Ok(())
 }
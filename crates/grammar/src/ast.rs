@@ -0,0 +1,175 @@
+//! Typed views over the untyped syntax tree.
+//!
+//! Every nonterminal gets a wrapper struct around its `SyntaxNode`,
+//! and every category of nonterminals (e.g. `Statement`) an enum of them.
+//! Accessors for named children are generated from the `fields` table
+//! in `meta/syntax.toml`. Since the tree may contain errors, every child
+//! is optional; a wrapper never owns more than the node it wraps.
+
+use {
+    crate::{
+        syntax::{SyntaxNode, SyntaxNodeChildren, SyntaxToken},
+        SyntaxKind,
+    },
+    std::marker::PhantomData,
+};
+
+/// A typed wrapper around a `SyntaxNode` of a specific kind.
+pub trait AstNode {
+    /// Whether nodes of this kind can be wrapped by this type.
+    fn can_cast(kind: SyntaxKind) -> bool
+    where
+        Self: Sized;
+
+    /// Wrap a node, if it is of the right kind.
+    fn cast(syntax: SyntaxNode) -> Option<Self>
+    where
+        Self: Sized;
+
+    /// The wrapped node.
+    fn syntax(&self) -> &SyntaxNode;
+}
+
+/// An iterator over the child nodes of a given AST type.
+#[derive(Debug, Clone)]
+pub struct AstChildren<N> {
+    inner: SyntaxNodeChildren,
+    marker: PhantomData<N>,
+}
+
+impl<N: AstNode> AstChildren<N> {
+    fn new(parent: &SyntaxNode) -> Self {
+        AstChildren {
+            inner: parent.children(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<N: AstNode> Iterator for AstChildren<N> {
+    type Item = N;
+
+    fn next(&mut self) -> Option<N> {
+        self.inner.find_map(N::cast)
+    }
+}
+
+mod support {
+    use super::*;
+
+    /// The `n`th child of type `N` that follows the token `after` and precedes the token `before`,
+    /// either of which can be omitted to start at the first child or end at the last.
+    pub(super) fn child<N: AstNode>(
+        parent: &SyntaxNode,
+        after: Option<SyntaxKind>,
+        before: Option<SyntaxKind>,
+        n: usize,
+    ) -> Option<N> {
+        let mut elements = parent.children_with_tokens();
+        // nodes never have the kind of a token
+        if after.is_some() {
+            elements.find(|it| Some(it.kind()) == after)?;
+        }
+        elements
+            .take_while(|it| Some(it.kind()) != before)
+            .filter_map(|it| it.into_node())
+            .filter_map(N::cast)
+            .nth(n)
+    }
+
+    pub(super) fn children<N: AstNode>(parent: &SyntaxNode) -> AstChildren<N> {
+        AstChildren::new(parent)
+    }
+
+    pub(super) fn token(parent: &SyntaxNode, kind: SyntaxKind, n: usize) -> Option<SyntaxToken> {
+        parent
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() == kind)
+            .nth(n)
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/ast.rs"));
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::syntax::GreenNodeBuilder};
+
+    /// Build a tree with `f`, whose children may be missing where the parser would not omit them.
+    fn tree(kind: SyntaxKind, f: impl FnOnce(&mut GreenNodeBuilder)) -> SyntaxNode {
+        let mut builder = GreenNodeBuilder::new();
+        builder.start_node(kind);
+        f(&mut builder);
+        builder.finish_node();
+        SyntaxNode::new_root(builder.finish())
+    }
+
+    fn identifier(builder: &mut GreenNodeBuilder, name: &str) {
+        builder.start_node(SyntaxKind::ExpressionTerm);
+        builder.start_node(SyntaxKind::TermIdentifier);
+        builder.token(SyntaxKind::Identifier, name);
+        builder.finish_node();
+        builder.finish_node();
+    }
+
+    #[test]
+    fn missing_then_branch() {
+        // if (a) else b;
+        let syntax = tree(SyntaxKind::StatementIf, |builder| {
+            builder.token(SyntaxKind::If, "if");
+            builder.start_node(SyntaxKind::ExpressionParenthesized);
+            builder.token(SyntaxKind::LeftParenthesis, "(");
+            identifier(builder, "a");
+            builder.token(SyntaxKind::RightParenthesis, ")");
+            builder.finish_node();
+            builder.token(SyntaxKind::Else, "else");
+            builder.start_node(SyntaxKind::StatementExpression);
+            identifier(builder, "b");
+            builder.token(SyntaxKind::Semicolon, ";");
+            builder.finish_node();
+        });
+        let stmt = StatementIf::cast(syntax).unwrap();
+        assert_eq!(stmt.cond().unwrap().syntax().to_string(), "(a)");
+        assert_eq!(stmt.then_branch(), None);
+        assert_eq!(stmt.else_branch().unwrap().syntax().to_string(), "b;");
+    }
+
+    #[test]
+    fn missing_else_branch() {
+        // if (a) b; else
+        let syntax = tree(SyntaxKind::StatementIf, |builder| {
+            builder.token(SyntaxKind::If, "if");
+            builder.start_node(SyntaxKind::StatementExpression);
+            identifier(builder, "b");
+            builder.token(SyntaxKind::Semicolon, ";");
+            builder.finish_node();
+            builder.token(SyntaxKind::Else, "else");
+        });
+        let stmt = StatementIf::cast(syntax).unwrap();
+        assert_eq!(stmt.cond(), None);
+        assert_eq!(stmt.then_branch().unwrap().syntax().to_string(), "b;");
+        assert_eq!(stmt.else_branch(), None);
+    }
+
+    #[test]
+    fn missing_operands() {
+        // + b
+        let syntax = tree(SyntaxKind::ExpressionAddition, |builder| {
+            builder.token(SyntaxKind::PlusSign, "+");
+            identifier(builder, "b");
+        });
+        let expr = ExpressionAddition::cast(syntax).unwrap();
+        assert_eq!(expr.lhs(), None);
+        assert_eq!(expr.rhs().unwrap().syntax().to_string(), "b");
+
+        // a -
+        let syntax = tree(SyntaxKind::ExpressionSubtraction, |builder| {
+            identifier(builder, "a");
+            builder.token(SyntaxKind::HyphenMinus, "-");
+        });
+        let expr = ExpressionSubtraction::cast(syntax).unwrap();
+        assert_eq!(expr.lhs().unwrap().syntax().to_string(), "a");
+        assert_eq!(expr.rhs(), None);
+    }
+}
@@ -1,3 +1,12 @@
+pub use crate::{
+    syntax::{SyntaxElement, SyntaxNode, SyntaxToken, TextRange, TextSize},
+    token_set::TokenSet,
+};
+
+pub mod ast;
+pub mod syntax;
+mod token_set;
+
 use {
     serde::{
         de::{Deserialize, Deserializer, EnumAccess, VariantAccess, Visitor},
@@ -0,0 +1,163 @@
+//! The lossless syntax tree, as provided by [rowan].
+//!
+//! Nodes are immutable and cheap to clone; a `SyntaxNode` is a cursor
+//! into a shared `GreenNode` that additionally knows its parent and offset.
+//! Every byte of the source text, trivia included, is owned by some token,
+//! so printing the tree reproduces the source text exactly.
+//!
+//!   [rowan]: <https://docs.rs/rowan>
+
+use {
+    crate::SyntaxKind,
+    rowan::Language,
+    serde::ser::{Error, Serialize, SerializeMap, Serializer},
+    std::convert::TryFrom,
+};
+
+pub use rowan::{Direction, GreenNode, GreenToken, NodeOrToken, TextRange, TextSize, WalkEvent};
+
+/// The [`rowan::Language`] tag for Tiny-C syntax trees.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum TinyC {}
+
+impl rowan::Language for TinyC {
+    type Kind = SyntaxKind;
+
+    fn kind_from_raw(raw: rowan::SyntaxKind) -> SyntaxKind {
+        SyntaxKind::try_from(raw.0).unwrap()
+    }
+
+    fn kind_to_raw(kind: SyntaxKind) -> rowan::SyntaxKind {
+        rowan::SyntaxKind(kind.into())
+    }
+}
+
+pub type SyntaxNode = rowan::SyntaxNode<TinyC>;
+pub type SyntaxToken = rowan::SyntaxToken<TinyC>;
+pub type SyntaxElement = rowan::SyntaxElement<TinyC>;
+pub type SyntaxNodeChildren = rowan::SyntaxNodeChildren<TinyC>;
+pub type SyntaxElementChildren = rowan::SyntaxElementChildren<TinyC>;
+
+/// A standalone green token, e.g. for replacing a token in an existing tree.
+pub fn green_token(kind: SyntaxKind, text: &str) -> GreenToken {
+    GreenToken::new(TinyC::kind_to_raw(kind), text)
+}
+
+/// Builds a `GreenNode` from a depth-first walk of the tree.
+#[derive(Debug, Default)]
+pub struct GreenNodeBuilder(rowan::GreenNodeBuilder<'static>);
+
+impl GreenNodeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn token(&mut self, kind: SyntaxKind, text: &str) {
+        self.0.token(TinyC::kind_to_raw(kind), text)
+    }
+
+    pub fn start_node(&mut self, kind: SyntaxKind) {
+        self.0.start_node(TinyC::kind_to_raw(kind))
+    }
+
+    pub fn finish_node(&mut self) {
+        self.0.finish_node()
+    }
+
+    pub fn finish(self) -> GreenNode {
+        self.0.finish()
+    }
+}
+
+/// Serializes a syntax tree as nested `Kind: [children]` and `Kind: text` maps.
+///
+/// Serializing a tree nested deeper than the parser ever nests one fails,
+/// rather than risk overflowing the stack.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Tree(pub SyntaxElement);
+
+/// How deeply a `Tree` may nest to be serialized.
+/// Serialization is recursive, so anything deeper would risk overflowing the stack.
+const MAX_DEPTH: u32 = 512;
+
+impl Serialize for Tree {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Subtree {
+            element: self.0.clone(),
+            depth: 0,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// An element of a `Tree` that is serialized, and how deeply it is nested.
+struct Subtree {
+    element: SyntaxElement,
+    depth: u32,
+}
+
+impl Serialize for Subtree {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if self.depth > MAX_DEPTH {
+            return Err(S::Error::custom(format_args!(
+                "the syntax tree is nested more than {} deep",
+                MAX_DEPTH,
+            )));
+        }
+        let mut map = serializer.serialize_map(Some(1))?;
+        match &self.element {
+            NodeOrToken::Node(node) => {
+                let children: Vec<_> = node
+                    .children_with_tokens()
+                    .map(|element| Subtree {
+                        element,
+                        depth: self.depth + 1,
+                    })
+                    .collect();
+                map.serialize_entry(node.kind().name(), &children)?;
+            }
+            NodeOrToken::Token(token) => {
+                map.serialize_entry(token.kind().name(), token.text())?;
+            }
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `depth` nested blocks around an empty statement.
+    fn nested(depth: usize) -> Tree {
+        let mut builder = GreenNodeBuilder::new();
+        builder.start_node(SyntaxKind::Program);
+        for _ in 0..depth {
+            builder.start_node(SyntaxKind::StatementBlock);
+        }
+        builder.start_node(SyntaxKind::StatementExpression);
+        builder.token(SyntaxKind::Semicolon, ";");
+        builder.finish_node();
+        for _ in 0..depth {
+            builder.finish_node();
+        }
+        builder.finish_node();
+        Tree(SyntaxNode::new_root(builder.finish()).into())
+    }
+
+    #[test]
+    fn too_deep() {
+        assert!(json::to_string(&nested(500)).is_ok());
+        let error = json::to_string(&nested(1000)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the syntax tree is nested more than 512 deep"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use {
+    crate::TokenKind,
+    serde::ser::{Serialize, Serializer},
+    std::{
+        iter::FromIterator,
+        ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign},
+    },
+};
+
+/// A set of `TokenKind`s.
+///
+/// Sets can be built in `const` contexts, so they can be used as constants,
+/// like the ones generated for the groups of tokens declared in `meta/syntax.toml`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct TokenSet(u128);
+
+// Every kind needs a bit of the set.
+const _: () = assert!(TokenKind::ALL.len() <= 128);
+
+impl TokenSet {
+    pub const EMPTY: TokenSet = TokenSet(0);
+
+    pub const fn new(kind: TokenKind) -> TokenSet {
+        TokenSet(1u128 << (kind as u16))
+    }
+
+    /// The set of the `kinds`.
+    pub const fn from_kinds(kinds: &[TokenKind]) -> TokenSet {
+        let mut set = TokenSet::EMPTY;
+        let mut i = 0;
+        while i < kinds.len() {
+            set = set.union(TokenSet::new(kinds[i]));
+            i += 1;
+        }
+        set
+    }
+
+    /// The kinds in either set, as `|`, which cannot be used in `const` contexts.
+    pub const fn union(self, other: TokenSet) -> TokenSet {
+        TokenSet(self.0 | other.0)
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn contains(self, kind: TokenKind) -> bool {
+        self.0 & TokenSet::new(kind).0 != 0
+    }
+
+    /// The kinds in this set, in declaration order.
+    pub fn iter(self) -> impl Iterator<Item = TokenKind> {
+        TokenKind::ALL
+            .iter()
+            .copied()
+            .filter(move |&kind| self.contains(kind))
+    }
+}
+
+impl Serialize for TokenSet {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl BitAnd for TokenSet {
+    type Output = TokenSet;
+
+    fn bitand(self, rhs: Self) -> TokenSet {
+        TokenSet(self.0 & rhs.0)
+    }
+}
+
+impl BitAndAssign for TokenSet {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = *self & rhs;
+    }
+}
+
+impl BitOr for TokenSet {
+    type Output = TokenSet;
+
+    fn bitor(self, rhs: Self) -> TokenSet {
+        self.union(rhs)
+    }
+}
+
+impl BitOrAssign for TokenSet {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = *self | rhs;
+    }
+}
+
+impl BitAnd<TokenKind> for TokenSet {
+    type Output = bool;
+
+    fn bitand(self, kind: TokenKind) -> bool {
+        self.contains(kind)
+    }
+}
+
+impl BitOr<TokenKind> for TokenSet {
+    type Output = TokenSet;
+
+    fn bitor(self, kind: TokenKind) -> TokenSet {
+        self | TokenSet::new(kind)
+    }
+}
+
+impl BitOrAssign<TokenKind> for TokenSet {
+    fn bitor_assign(&mut self, kind: TokenKind) {
+        *self = *self | kind;
+    }
+}
+
+impl FromIterator<TokenKind> for TokenSet {
+    fn from_iter<I: IntoIterator<Item = TokenKind>>(iter: I) -> Self {
+        let mut set = TokenSet::EMPTY;
+        for kind in iter.into_iter() {
+            set |= kind;
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{TokenKind, TokenKind::*, TokenSet};
+
+    const BRACKETS: TokenSet = TokenSet::from_kinds(&[LeftParenthesis, RightParenthesis])
+        .union(TokenSet::new(LeftCurlyBracket))
+        .union(TokenSet::EMPTY);
+
+    fn kinds(set: TokenSet) -> Vec<TokenKind> {
+        set.iter().collect()
+    }
+
+    #[test]
+    fn const_construction() {
+        assert_eq!(
+            kinds(BRACKETS),
+            [LeftCurlyBracket, LeftParenthesis, RightParenthesis]
+        );
+        const _: () = assert!(BRACKETS.contains(LeftParenthesis) && !BRACKETS.contains(Semicolon));
+        const _: () = assert!(TokenSet::EMPTY.is_empty() && !BRACKETS.is_empty());
+        assert_eq!(
+            BRACKETS,
+            [LeftParenthesis, RightParenthesis, LeftCurlyBracket]
+                .iter()
+                .copied()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn generated_sets() {
+        assert_eq!(kinds(TokenSet::KEYWORDS), [Else, If, While]);
+        assert_eq!(kinds(TokenSet::LITERALS), [Integer]);
+        assert_eq!(
+            kinds(TokenSet::TRIVIA),
+            [Whitespace, LineComment, BlockComment]
+        );
+        assert_eq!(TokenSet::PUNCTUATION.iter().count(), 9);
+        assert_eq!(
+            kinds(TokenSet::EXPRESSION_FIRST),
+            [Integer, LeftParenthesis, Identifier],
+        );
+        assert_eq!(
+            kinds(TokenSet::STATEMENT_FIRST),
+            [
+                If,
+                While,
+                Integer,
+                LeftCurlyBracket,
+                LeftParenthesis,
+                Semicolon,
+                Identifier
+            ],
+        );
+        assert_eq!(
+            kinds(TokenSet::EXPRESSION_RECOVERY),
+            [Else, If, While, RightParenthesis, Semicolon],
+        );
+    }
+}
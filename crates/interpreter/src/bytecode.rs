@@ -0,0 +1,215 @@
+use {
+    crate::AssembleError,
+    std::{convert::TryFrom, fmt, str::FromStr},
+};
+
+/// An instruction of the Tiny-C stack machine.
+///
+/// The encoding mirrors the original: every instruction takes one code slot,
+/// plus one more for its operand if it has one. Jump offsets are relative
+/// to the slot holding the offset, so `Jmp(-1)` jumps back to its own opcode.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Instruction {
+    /// Push the value of the variable with the given index.
+    IFetch(u8),
+    /// Store the top of the stack to the variable with the given index, without popping it.
+    IStore(u8),
+    /// Push a constant.
+    IPush(i32),
+    /// Pop and discard the top of the stack.
+    IPop,
+    /// Pop two values and push their sum.
+    IAdd,
+    /// Pop two values and push their difference.
+    ISub,
+    /// Pop two values and push `1` if the first is less than the second, else `0`.
+    ILt,
+    /// Pop a value and jump if it is zero.
+    Jz(i32),
+    /// Pop a value and jump if it is nonzero.
+    Jnz(i32),
+    /// Jump unconditionally.
+    Jmp(i32),
+    /// Stop execution.
+    Halt,
+}
+
+/// A compiled Tiny-C program.
+///
+/// `Display` prints a disassembly with one instruction per line, prefixed by its address:
+///
+/// ```text
+///    0  IPUSH 1
+///    2  ISTORE 8
+///    4  IPOP
+///    5  HALT
+/// ```
+///
+/// Jumps are printed with their raw relative offset and a comment with the target address.
+/// `FromStr` parses the same format back; addresses and comments are optional.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Bytecode {
+    instructions: Vec<Instruction>,
+}
+
+impl Instruction {
+    /// The mnemonic of this instruction as used by the original.
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Instruction::IFetch(_) => "IFETCH",
+            Instruction::IStore(_) => "ISTORE",
+            Instruction::IPush(_) => "IPUSH",
+            Instruction::IPop => "IPOP",
+            Instruction::IAdd => "IADD",
+            Instruction::ISub => "ISUB",
+            Instruction::ILt => "ILT",
+            Instruction::Jz(_) => "JZ",
+            Instruction::Jnz(_) => "JNZ",
+            Instruction::Jmp(_) => "JMP",
+            Instruction::Halt => "HALT",
+        }
+    }
+
+    /// The operand of this instruction, if it has one.
+    pub fn operand(self) -> Option<i32> {
+        match self {
+            Instruction::IFetch(var) | Instruction::IStore(var) => Some(var.into()),
+            Instruction::IPush(value) => Some(value),
+            Instruction::Jz(offset) | Instruction::Jnz(offset) | Instruction::Jmp(offset) => {
+                Some(offset)
+            }
+            Instruction::IPop
+            | Instruction::IAdd
+            | Instruction::ISub
+            | Instruction::ILt
+            | Instruction::Halt => None,
+        }
+    }
+
+    /// The number of code slots this instruction takes.
+    pub fn size(self) -> u32 {
+        if self.operand().is_some() {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// The target address of this instruction, if it is a jump at `address`.
+    pub fn jump_target(self, address: u32) -> Option<i64> {
+        match self {
+            Instruction::Jz(offset) | Instruction::Jnz(offset) | Instruction::Jmp(offset) => {
+                Some(i64::from(address) + 1 + i64::from(offset))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Bytecode {
+    pub fn new(instructions: Vec<Instruction>) -> Self {
+        Bytecode { instructions }
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Every instruction along with its address.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, Instruction)> + '_ {
+        self.instructions.iter().scan(0, |address, &instruction| {
+            let this = *address;
+            *address += instruction.size();
+            Some((this, instruction))
+        })
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.mnemonic())?;
+        if let Some(operand) = self.operand() {
+            write!(f, " {}", operand)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Bytecode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (address, instruction) in self.iter() {
+            write!(f, "{:>4}  {}", address, instruction)?;
+            if let Some(target) = instruction.jump_target(address) {
+                write!(f, "  ; {}", target)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Bytecode {
+    type Err = AssembleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut instructions = vec![];
+        let mut address = 0;
+        for (line, text) in s.lines().enumerate() {
+            let error = |message: String| AssembleError { line, message };
+            let text = text.split(';').next().unwrap();
+            let mut words = text.split_whitespace().peekable();
+            if words.peek().is_none() {
+                continue;
+            }
+            if let Some(Ok(explicit)) = words.peek().map(|word| word.parse::<u32>()) {
+                if explicit != address {
+                    return Err(error(format!(
+                        "address {} does not match actual address {}",
+                        explicit, address,
+                    )));
+                }
+                words.next();
+            }
+            let mnemonic = words
+                .next()
+                .ok_or_else(|| error("expected an instruction".to_string()))?;
+            let operand = words.next();
+            let int = || -> Result<i32, AssembleError> {
+                let word =
+                    operand.ok_or_else(|| error(format!("`{}` requires an operand", mnemonic)))?;
+                word.parse()
+                    .map_err(|_| error(format!("invalid operand `{}`", word)))
+            };
+            let variable = || -> Result<u8, AssembleError> {
+                let var = int()?;
+                match u8::try_from(var) {
+                    Ok(var) if var < 26 => Ok(var),
+                    _ => Err(error(format!("invalid variable index `{}`", var))),
+                }
+            };
+            let instruction = match mnemonic {
+                "IFETCH" => Instruction::IFetch(variable()?),
+                "ISTORE" => Instruction::IStore(variable()?),
+                "IPUSH" => Instruction::IPush(int()?),
+                "IPOP" => Instruction::IPop,
+                "IADD" => Instruction::IAdd,
+                "ISUB" => Instruction::ISub,
+                "ILT" => Instruction::ILt,
+                "JZ" => Instruction::Jz(int()?),
+                "JNZ" => Instruction::Jnz(int()?),
+                "JMP" => Instruction::Jmp(int()?),
+                "HALT" => Instruction::Halt,
+                _ => return Err(error(format!("unknown instruction `{}`", mnemonic))),
+            };
+            if operand.is_some() && instruction.operand().is_none() {
+                return Err(error(format!("`{}` does not take an operand", mnemonic)));
+            }
+            if let Some(word) = words.next() {
+                return Err(error(format!("unexpected `{}` after instruction", word)));
+            }
+            address += instruction.size();
+            instructions.push(instruction);
+        }
+        Ok(Bytecode { instructions })
+    }
+}
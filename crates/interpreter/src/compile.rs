@@ -0,0 +1,168 @@
+use {
+    crate::{
+        chain, integer, required, variable, Binary, Bytecode, Instruction, Operation, RuntimeError,
+    },
+    std::convert::TryFrom,
+    tinyc_grammar::ast::{self, AstNode},
+};
+
+/// Compile a program to bytecode for the stack machine.
+///
+/// The generated code matches the original Tiny-C code generator
+/// instruction for instruction. Unlike the interpreter, which only
+/// checks the code it reaches, this fails on any unknown variable,
+/// oversized integer literal, or incomplete syntax in the program.
+pub fn compile(program: &ast::Program) -> Result<Bytecode, RuntimeError> {
+    let mut compiler = Compiler::default();
+    for statement in program.statements() {
+        compiler.statement(&statement)?;
+    }
+    compiler.emit(Instruction::Halt);
+    Ok(Bytecode::new(compiler.code))
+}
+
+#[derive(Debug, Default)]
+struct Compiler {
+    code: Vec<Instruction>,
+    here: u32,
+}
+
+/// A jump whose offset is yet to be filled in.
+#[derive(Debug)]
+struct Hole {
+    index: usize,
+    address: u32,
+}
+
+impl Compiler {
+    fn emit(&mut self, instruction: Instruction) {
+        self.here += instruction.size();
+        self.code.push(instruction);
+    }
+
+    /// Emit a jump with an offset to be filled in by `fix`.
+    fn hole(&mut self, jump: fn(i32) -> Instruction) -> Hole {
+        let hole = Hole {
+            index: self.code.len(),
+            address: self.here + 1,
+        };
+        self.emit(jump(0));
+        hole
+    }
+
+    /// Point the jump at `hole` to `target`.
+    fn fix(&mut self, hole: Hole, target: u32) {
+        let offset = i64::from(target) - i64::from(hole.address);
+        let offset = i32::try_from(offset).expect("jump offset out of range");
+        self.code[hole.index] = match self.code[hole.index] {
+            Instruction::Jz(_) => Instruction::Jz(offset),
+            Instruction::Jnz(_) => Instruction::Jnz(offset),
+            Instruction::Jmp(_) => Instruction::Jmp(offset),
+            instruction => unreachable!("hole at non-jump instruction {}", instruction),
+        };
+    }
+
+    fn statement(&mut self, statement: &ast::Statement) -> Result<(), RuntimeError> {
+        let syntax = statement.syntax();
+        match statement {
+            ast::Statement::If(it) => {
+                self.condition(required(it.cond(), syntax)?)?;
+                let to_else = self.hole(Instruction::Jz);
+                self.statement(&required(it.then_branch(), syntax)?)?;
+                match it.else_branch() {
+                    None => self.fix(to_else, self.here),
+                    Some(else_branch) => {
+                        let to_end = self.hole(Instruction::Jmp);
+                        self.fix(to_else, self.here);
+                        self.statement(&else_branch)?;
+                        self.fix(to_end, self.here);
+                    }
+                }
+            }
+            ast::Statement::While(it) => {
+                let start = self.here;
+                self.condition(required(it.cond(), syntax)?)?;
+                let to_end = self.hole(Instruction::Jz);
+                self.statement(&required(it.body(), syntax)?)?;
+                let to_start = self.hole(Instruction::Jmp);
+                self.fix(to_start, start);
+                self.fix(to_end, self.here);
+            }
+            ast::Statement::Block(it) => {
+                for statement in it.statements() {
+                    self.statement(&statement)?;
+                }
+            }
+            ast::Statement::Expression(it) => {
+                if let Some(expr) = it.expr() {
+                    self.expression(&expr)?;
+                    self.emit(Instruction::IPop);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn condition(&mut self, cond: ast::ExpressionParenthesized) -> Result<(), RuntimeError> {
+        let expr = required(cond.expr(), cond.syntax())?;
+        self.expression(&expr)
+    }
+
+    fn expression(&mut self, expression: &ast::Expression) -> Result<(), RuntimeError> {
+        let (first, operations) = chain(expression)?;
+        self.operand(&first)?;
+        for Operation {
+            operator,
+            rhs,
+            syntax,
+        } in operations
+        {
+            self.expression(&required(rhs, &syntax)?)?;
+            self.emit(match operator {
+                Binary::Comparison => Instruction::ILt,
+                Binary::Addition => Instruction::IAdd,
+                Binary::Subtraction => Instruction::ISub,
+            });
+        }
+        Ok(())
+    }
+
+    /// An expression that is not a binary operator.
+    fn operand(&mut self, expression: &ast::Expression) -> Result<(), RuntimeError> {
+        let syntax = expression.syntax();
+        match expression {
+            ast::Expression::Parenthesized(it) => {
+                self.expression(&required(it.expr(), syntax)?)?;
+            }
+            ast::Expression::Assignment(it) => {
+                let variable = variable(&required(it.id(), syntax)?)?;
+                self.expression(&required(it.val(), syntax)?)?;
+                self.emit(Instruction::IStore(variable as u8));
+            }
+            ast::Expression::Term(it) => self.term(&required(it.term(), syntax)?)?,
+            ast::Expression::Comparison(_)
+            | ast::Expression::Addition(_)
+            | ast::Expression::Subtraction(_) => unreachable!("binary operators are chained"),
+        }
+        Ok(())
+    }
+
+    fn term(&mut self, term: &ast::Term) -> Result<(), RuntimeError> {
+        let syntax = term.syntax();
+        match term {
+            ast::Term::Identifier(it) => {
+                let variable = variable(&required(it.identifier(), syntax)?)?;
+                self.emit(Instruction::IFetch(variable as u8));
+            }
+            ast::Term::Integer(it) => {
+                let value = integer(&required(it.integer(), syntax)?)?;
+                self.emit(Instruction::IPush(value));
+            }
+            ast::Term::Expression(it) => {
+                let expr = required(it.expr(), syntax)?;
+                self.expression(&ast::Expression::from(expr))?;
+            }
+        }
+        Ok(())
+    }
+}
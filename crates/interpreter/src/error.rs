@@ -0,0 +1,154 @@
+use {
+    std::fmt,
+    tinyc_diagnostics::{Diagnostic, Label},
+    tinyc_grammar::TextRange,
+    tinyc_parser::SyntaxError,
+};
+
+/// An error encountered while running a program.
+#[derive(serde::Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RuntimeError {
+    /// What went wrong.
+    pub kind: RuntimeErrorKind,
+    /// The range of the node that could not be evaluated.
+    pub range: TextRange,
+}
+
+/// The kind of a `RuntimeError`.
+#[derive(serde::Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum RuntimeErrorKind {
+    /// The syntax tree is missing a piece required to evaluate it.
+    Incomplete,
+    /// An identifier that is not one of the variables `a` through `z`.
+    UnknownVariable { name: String },
+    /// An integer literal that does not fit in an `int`.
+    IntegerOverflow { literal: String },
+}
+
+/// An error encountered while executing bytecode.
+#[derive(serde::Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VmError {
+    /// What went wrong.
+    pub kind: VmErrorKind,
+    /// The address of the failing instruction.
+    pub address: u32,
+}
+
+/// The kind of a `VmError`.
+#[derive(serde::Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum VmErrorKind {
+    /// An instruction needed more values than were on the stack.
+    StackUnderflow,
+    /// A variable index that is not less than 26.
+    InvalidVariable { index: u8 },
+    /// A jump to an address that is not the start of an instruction.
+    InvalidJump { target: i64 },
+    /// Execution ran past the last instruction without reaching `HALT`.
+    MissingHalt,
+}
+
+/// An error encountered while parsing bytecode disassembly.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AssembleError {
+    /// The zero-based line the error is on.
+    pub line: usize,
+    pub message: String,
+}
+
+/// An error encountered while parsing or running a program.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Error {
+    /// The program has syntax errors, so was not run.
+    Syntax(Vec<SyntaxError>),
+    /// The program failed while running.
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            RuntimeErrorKind::Incomplete => write!(f, "cannot evaluate incomplete syntax"),
+            RuntimeErrorKind::UnknownVariable { name } => {
+                write!(
+                    f,
+                    "unknown variable `{}`; variables are `a` through `z`",
+                    name
+                )
+            }
+            RuntimeErrorKind::IntegerOverflow { literal } => {
+                write!(f, "integer literal `{}` is too large for an `int`", literal)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax(errors) => {
+                for (i, error) in errors.iter().enumerate() {
+                    if i != 0 {
+                        writeln!(f)?;
+                    }
+                    fmt::Display::fmt(error, f)?;
+                }
+                Ok(())
+            }
+            Error::Runtime(error) => fmt::Display::fmt(error, f),
+        }
+    }
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            VmErrorKind::StackUnderflow => write!(f, "stack underflow")?,
+            VmErrorKind::InvalidVariable { index } => {
+                write!(f, "invalid variable index {}", index)?
+            }
+            VmErrorKind::InvalidJump { target } => write!(f, "invalid jump target {}", target)?,
+            VmErrorKind::MissingHalt => write!(f, "ran past the end of the code")?,
+        }
+        write!(f, " at address {}", self.address)
+    }
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line + 1, self.message)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(error: RuntimeError) -> Self {
+        Error::Runtime(error)
+    }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+    fn from(error: &RuntimeError) -> Self {
+        match &error.kind {
+            RuntimeErrorKind::Incomplete => Diagnostic::error("cannot evaluate incomplete syntax")
+                .with_label(Label::primary(error.range, "this has syntax errors")),
+            RuntimeErrorKind::UnknownVariable { name } => {
+                Diagnostic::error(format!("unknown variable `{}`", name))
+                    .with_label(Label::primary(error.range, "not a variable"))
+                    .with_note("Tiny-C has exactly 26 variables, `a` through `z`")
+            }
+            RuntimeErrorKind::IntegerOverflow { literal } => {
+                Diagnostic::error(format!("integer literal `{}` is too large", literal))
+                    .with_label(Label::primary(error.range, "does not fit in an `int`"))
+                    .with_note(format!("the largest `int` is {}", i32::MAX))
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+impl std::error::Error for VmError {}
+impl std::error::Error for AssembleError {}
+impl std::error::Error for Error {}
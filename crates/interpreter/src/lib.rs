@@ -0,0 +1,314 @@
+//! A tree-walking interpreter for Tiny-C.
+//!
+//! Tiny-C has exactly 26 global `int` variables, `a` through `z`, all starting at zero.
+//! Running a program executes its statements in order and produces the final variables.
+//! Conditions are true when nonzero, `<` evaluates to `0` or `1`,
+//! and an assignment evaluates to the assigned value.
+//! Arithmetic wraps on overflow rather than being undefined.
+//!
+//! Programs can also be `compile`d to `Bytecode` for the same stack machine
+//! as the original Tiny-C, and `execute`d there.
+
+pub use crate::{
+    bytecode::{Bytecode, Instruction},
+    compile::compile,
+    error::{AssembleError, Error, RuntimeError, RuntimeErrorKind, VmError, VmErrorKind},
+    variables::Variables,
+    vm::execute,
+};
+
+use tinyc_grammar::{
+    ast::{self, AstNode},
+    SyntaxNode, SyntaxToken,
+};
+
+mod bytecode;
+mod compile;
+mod error;
+mod variables;
+mod vm;
+
+/// Run a program, returning its final variables.
+///
+/// Parts of the tree that failed to parse are reported as `RuntimeErrorKind::Incomplete`
+/// only if they are actually reached while running.
+pub fn run(program: &ast::Program) -> Result<Variables, RuntimeError> {
+    let mut interpreter = Interpreter::default();
+    for statement in program.statements() {
+        interpreter.statement(&statement)?;
+    }
+    Ok(interpreter.variables)
+}
+
+/// Parse and run a program, returning its final variables.
+///
+/// Programs with syntax errors are not run.
+pub fn run_text(text: &str) -> Result<Variables, Error> {
+    let parse = tinyc_parser::parse_text(text);
+    if !parse.errors().is_empty() {
+        return Err(Error::Syntax(parse.errors().to_vec()));
+    }
+    let program = ast::Program::cast(parse.syntax()).unwrap();
+    Ok(run(&program)?)
+}
+
+/// Parse and compile a program to bytecode.
+///
+/// Programs with syntax errors are not compiled.
+pub fn compile_text(text: &str) -> Result<Bytecode, Error> {
+    let parse = tinyc_parser::parse_text(text);
+    if !parse.errors().is_empty() {
+        return Err(Error::Syntax(parse.errors().to_vec()));
+    }
+    let program = ast::Program::cast(parse.syntax()).unwrap();
+    Ok(compile(&program)?)
+}
+
+#[derive(Debug, Default)]
+struct Interpreter {
+    variables: Variables,
+}
+
+/// Require a child that may be missing from a tree with errors.
+fn required<T>(child: Option<T>, parent: &SyntaxNode) -> Result<T, RuntimeError> {
+    child.ok_or_else(|| RuntimeError {
+        kind: RuntimeErrorKind::Incomplete,
+        range: parent.text_range(),
+    })
+}
+
+/// The index of the variable named by an identifier.
+fn variable(id: &SyntaxToken) -> Result<usize, RuntimeError> {
+    Variables::index_of(id.text()).ok_or_else(|| RuntimeError {
+        kind: RuntimeErrorKind::UnknownVariable {
+            name: id.text().to_string(),
+        },
+        range: id.text_range(),
+    })
+}
+
+/// The value of an integer literal.
+fn integer(literal: &SyntaxToken) -> Result<i32, RuntimeError> {
+    literal.text().parse().map_err(|_| RuntimeError {
+        kind: RuntimeErrorKind::IntegerOverflow {
+            literal: literal.text().to_string(),
+        },
+        range: literal.text_range(),
+    })
+}
+
+/// A binary operator.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Binary {
+    Comparison,
+    Addition,
+    Subtraction,
+}
+
+/// A binary operator and its right operand, in a chain of them like `a+b-c`.
+#[derive(Debug)]
+struct Operation {
+    operator: Binary,
+    rhs: Option<ast::Expression>,
+    /// The binary expression, for errors about its operands.
+    syntax: SyntaxNode,
+}
+
+/// Split a chain of binary operators into its first operand and the operations after it.
+///
+/// Binary operators nest on the left, one level per operator,
+/// so this walks down the chain instead of recursing for every operator.
+fn chain(expression: &ast::Expression) -> Result<(ast::Expression, Vec<Operation>), RuntimeError> {
+    let mut operations = vec![];
+    let mut expression = expression.clone();
+    loop {
+        let (operator, lhs, rhs) = match &expression {
+            ast::Expression::Comparison(it) => (Binary::Comparison, it.lhs(), it.rhs()),
+            ast::Expression::Addition(it) => (Binary::Addition, it.lhs(), it.rhs()),
+            ast::Expression::Subtraction(it) => (Binary::Subtraction, it.lhs(), it.rhs()),
+            _ => break,
+        };
+        let syntax = expression.syntax().clone();
+        expression = required(lhs, &syntax)?;
+        operations.push(Operation {
+            operator,
+            rhs,
+            syntax,
+        });
+    }
+    operations.reverse();
+    Ok((expression, operations))
+}
+
+impl Interpreter {
+    fn statement(&mut self, statement: &ast::Statement) -> Result<(), RuntimeError> {
+        let syntax = statement.syntax();
+        match statement {
+            ast::Statement::If(it) => {
+                if self.condition(required(it.cond(), syntax)?)? {
+                    self.statement(&required(it.then_branch(), syntax)?)?;
+                } else if let Some(else_branch) = it.else_branch() {
+                    self.statement(&else_branch)?;
+                }
+            }
+            ast::Statement::While(it) => {
+                let cond = required(it.cond(), syntax)?;
+                let body = required(it.body(), syntax)?;
+                while self.condition(cond.clone())? {
+                    self.statement(&body)?;
+                }
+            }
+            ast::Statement::Block(it) => {
+                for statement in it.statements() {
+                    self.statement(&statement)?;
+                }
+            }
+            ast::Statement::Expression(it) => {
+                // the empty statement `;` has no expression
+                if let Some(expr) = it.expr() {
+                    self.expression(&expr)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn condition(&mut self, cond: ast::ExpressionParenthesized) -> Result<bool, RuntimeError> {
+        let expr = required(cond.expr(), cond.syntax())?;
+        Ok(self.expression(&expr)? != 0)
+    }
+
+    fn expression(&mut self, expression: &ast::Expression) -> Result<i32, RuntimeError> {
+        let (first, operations) = chain(expression)?;
+        let mut value = self.operand(&first)?;
+        for Operation {
+            operator,
+            rhs,
+            syntax,
+        } in operations
+        {
+            let rhs = self.expression(&required(rhs, &syntax)?)?;
+            value = match operator {
+                Binary::Comparison => (value < rhs).into(),
+                Binary::Addition => value.wrapping_add(rhs),
+                Binary::Subtraction => value.wrapping_sub(rhs),
+            };
+        }
+        Ok(value)
+    }
+
+    /// An expression that is not a binary operator.
+    fn operand(&mut self, expression: &ast::Expression) -> Result<i32, RuntimeError> {
+        let syntax = expression.syntax();
+        Ok(match expression {
+            ast::Expression::Parenthesized(it) => self.expression(&required(it.expr(), syntax)?)?,
+            ast::Expression::Assignment(it) => {
+                let variable = variable(&required(it.id(), syntax)?)?;
+                let value = self.expression(&required(it.val(), syntax)?)?;
+                self.variables.set(variable, value);
+                value
+            }
+            ast::Expression::Term(it) => self.term(&required(it.term(), syntax)?)?,
+            ast::Expression::Comparison(_)
+            | ast::Expression::Addition(_)
+            | ast::Expression::Subtraction(_) => unreachable!("binary operators are chained"),
+        })
+    }
+
+    fn term(&mut self, term: &ast::Term) -> Result<i32, RuntimeError> {
+        let syntax = term.syntax();
+        Ok(match term {
+            ast::Term::Identifier(it) => {
+                let variable = variable(&required(it.identifier(), syntax)?)?;
+                self.variables[variable]
+            }
+            ast::Term::Integer(it) => integer(&required(it.integer(), syntax)?)?,
+            ast::Term::Expression(it) => {
+                let expr = required(it.expr(), syntax)?;
+                self.expression(&ast::Expression::from(expr))?
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{compile, run, run_text, Error, Instruction, RuntimeErrorKind, Variables},
+        tinyc_grammar::{
+            ast::{self, AstNode},
+            syntax::GreenNodeBuilder,
+            SyntaxKind, SyntaxNode,
+        },
+    };
+
+    /// `a=1+1+…+1;` with `len` ones, built directly,
+    /// as the parser does not nest operators this deeply.
+    fn long_chain(len: usize) -> ast::Program {
+        fn one(builder: &mut GreenNodeBuilder) {
+            builder.start_node(SyntaxKind::ExpressionTerm);
+            builder.start_node(SyntaxKind::TermInteger);
+            builder.token(SyntaxKind::Integer, "1");
+            builder.finish_node();
+            builder.finish_node();
+        }
+        let mut builder = GreenNodeBuilder::new();
+        builder.start_node(SyntaxKind::Program);
+        builder.start_node(SyntaxKind::StatementExpression);
+        builder.start_node(SyntaxKind::ExpressionAssignment);
+        builder.token(SyntaxKind::Identifier, "a");
+        builder.token(SyntaxKind::EqualsSign, "=");
+        for _ in 1..len {
+            builder.start_node(SyntaxKind::ExpressionAddition);
+        }
+        one(&mut builder);
+        for _ in 1..len {
+            builder.token(SyntaxKind::PlusSign, "+");
+            one(&mut builder);
+            builder.finish_node();
+        }
+        builder.finish_node();
+        builder.token(SyntaxKind::Semicolon, ";");
+        builder.finish_node();
+        builder.finish_node();
+        ast::Program::cast(SyntaxNode::new_root(builder.finish())).unwrap()
+    }
+
+    #[test]
+    fn run_long_chain() {
+        let mut expected = Variables::default();
+        expected.set(0, 3_000);
+        assert_eq!(run(&long_chain(3_000)), Ok(expected));
+    }
+
+    #[test]
+    fn compile_long_chain() {
+        let code = compile(&long_chain(3_000)).unwrap();
+        let adds = code
+            .instructions()
+            .iter()
+            .filter(|&&it| it == Instruction::IAdd);
+        assert_eq!(adds.count(), 2_999);
+    }
+
+    #[test]
+    fn errors() {
+        let runtime_error = |text| match run_text(text) {
+            Err(Error::Runtime(error)) => error.kind,
+            result => panic!("expected a runtime error, got {:?}", result),
+        };
+        assert_eq!(
+            runtime_error("ab=1;"),
+            RuntimeErrorKind::UnknownVariable { name: "ab".into() },
+        );
+        assert_eq!(
+            runtime_error("a=2147483648;"),
+            RuntimeErrorKind::IntegerOverflow {
+                literal: "2147483648".into()
+            },
+        );
+        assert!(matches!(run_text("a=;"), Err(Error::Syntax(_))));
+        // only reached code needs to be well-formed
+        assert!(run_text("if (0) ab=1;").is_ok());
+    }
+}
@@ -0,0 +1,66 @@
+use {
+    serde::ser::{Serialize, SerializeMap, Serializer},
+    std::ops::Index,
+};
+
+/// The state of a Tiny-C program: its 26 global `int` variables, `a` through `z`.
+///
+/// Like the original, only nonzero variables are serialized,
+/// so a fresh `Variables` serializes as an empty map.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Variables([i32; 26]);
+
+impl Variables {
+    /// All variables set to zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The index of the variable named `name`, if it is one.
+    pub fn index_of(name: &str) -> Option<usize> {
+        match name.as_bytes() {
+            &[c] if c.is_ascii_lowercase() => Some(usize::from(c - b'a')),
+            _ => None,
+        }
+    }
+
+    /// The value of the variable named `name`, if it is one.
+    pub fn get(&self, name: &str) -> Option<i32> {
+        Self::index_of(name).map(|ix| self.0[ix])
+    }
+
+    /// Set the variable at `index` (see `index_of`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than 26.
+    pub fn set(&mut self, index: usize, value: i32) {
+        self.0[index] = value;
+    }
+
+    /// Every variable's name and value, in alphabetical order.
+    pub fn iter(&self) -> impl Iterator<Item = (char, i32)> + '_ {
+        (b'a'..=b'z').map(char::from).zip(self.0.iter().copied())
+    }
+}
+
+impl Index<usize> for Variables {
+    type Output = i32;
+
+    fn index(&self, index: usize) -> &i32 {
+        &self.0[index]
+    }
+}
+
+impl Serialize for Variables {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        for (name, value) in self.iter().filter(|&(_, value)| value != 0) {
+            map.serialize_entry(&name, &value)?;
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{Bytecode, Instruction, Variables, VmError, VmErrorKind};
+
+/// Execute bytecode on the stack machine, returning the final variables.
+///
+/// Bytecode from `compile` always runs to `HALT`, though it may loop forever;
+/// errors are only possible for hand-written bytecode.
+pub fn execute(bytecode: &Bytecode) -> Result<Variables, VmError> {
+    let mut vm = Vm {
+        addresses: bytecode.iter().map(|(address, _)| address).collect(),
+        instructions: bytecode.instructions(),
+        stack: vec![],
+        variables: Variables::new(),
+        address: 0,
+    };
+    let mut pc = 0;
+    loop {
+        let instruction = match vm.instructions.get(pc) {
+            Some(&instruction) => instruction,
+            None => return Err(vm.error(VmErrorKind::MissingHalt)),
+        };
+        vm.address = vm.addresses[pc];
+        pc += 1;
+        match instruction {
+            Instruction::IFetch(var) => {
+                let value = vm.variables[vm.variable(var)?];
+                vm.stack.push(value);
+            }
+            Instruction::IStore(var) => {
+                let var = vm.variable(var)?;
+                let value = vm.pop()?;
+                vm.variables.set(var, value);
+                vm.stack.push(value);
+            }
+            Instruction::IPush(value) => vm.stack.push(value),
+            Instruction::IPop => {
+                vm.pop()?;
+            }
+            Instruction::IAdd => {
+                let (lhs, rhs) = vm.pop2()?;
+                vm.stack.push(lhs.wrapping_add(rhs));
+            }
+            Instruction::ISub => {
+                let (lhs, rhs) = vm.pop2()?;
+                vm.stack.push(lhs.wrapping_sub(rhs));
+            }
+            Instruction::ILt => {
+                let (lhs, rhs) = vm.pop2()?;
+                vm.stack.push((lhs < rhs).into());
+            }
+            Instruction::Jz(_) => {
+                if vm.pop()? == 0 {
+                    pc = vm.jump(instruction)?;
+                }
+            }
+            Instruction::Jnz(_) => {
+                if vm.pop()? != 0 {
+                    pc = vm.jump(instruction)?;
+                }
+            }
+            Instruction::Jmp(_) => pc = vm.jump(instruction)?,
+            Instruction::Halt => return Ok(vm.variables),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Vm<'a> {
+    /// The address of each instruction.
+    addresses: Vec<u32>,
+    instructions: &'a [Instruction],
+    stack: Vec<i32>,
+    variables: Variables,
+    /// The address of the executing instruction.
+    address: u32,
+}
+
+impl Vm<'_> {
+    fn error(&self, kind: VmErrorKind) -> VmError {
+        VmError {
+            kind,
+            address: self.address,
+        }
+    }
+
+    fn pop(&mut self) -> Result<i32, VmError> {
+        match self.stack.pop() {
+            Some(value) => Ok(value),
+            None => Err(self.error(VmErrorKind::StackUnderflow)),
+        }
+    }
+
+    fn pop2(&mut self) -> Result<(i32, i32), VmError> {
+        let rhs = self.pop()?;
+        let lhs = self.pop()?;
+        Ok((lhs, rhs))
+    }
+
+    fn variable(&self, var: u8) -> Result<usize, VmError> {
+        if var < 26 {
+            Ok(var.into())
+        } else {
+            Err(self.error(VmErrorKind::InvalidVariable { index: var }))
+        }
+    }
+
+    /// The index of the instruction targeted by a jump.
+    fn jump(&self, instruction: Instruction) -> Result<usize, VmError> {
+        let target = instruction.jump_target(self.address).unwrap();
+        self.addresses
+            .binary_search_by_key(&target, |&address| address.into())
+            .map_err(|_| self.error(VmErrorKind::InvalidJump { target }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{execute, Bytecode, VmErrorKind};
+
+    #[test]
+    fn errors() {
+        let error = |asm: &str| execute(&asm.parse::<Bytecode>().unwrap()).unwrap_err();
+        assert_eq!(error("IPUSH 1").kind, VmErrorKind::MissingHalt);
+        assert_eq!(error("IPUSH 1\nIADD").kind, VmErrorKind::StackUnderflow);
+        let jump = error("IPUSH 1\nJMP 2\nHALT");
+        assert_eq!(jump.kind, VmErrorKind::InvalidJump { target: 5 });
+        assert_eq!(jump.address, 2);
+    }
+}
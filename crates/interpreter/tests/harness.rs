@@ -0,0 +1,43 @@
+use {
+    tinyc_diagnostics::{Diagnostic, Renderer, SourceFile},
+    tinyc_grammar::ast::{AstNode, Program},
+    tinyc_interpreter::{
+        compile_text, execute, run_text, Bytecode, Error, RuntimeError, Variables,
+    },
+};
+
+#[conformance::tests(exact, format=yaml, file="../../tests/KartikTalwar.test", section=variables)]
+fn run(s: &str) -> Result<Variables, Error> {
+    run_text(s)
+}
+
+#[conformance::tests(exact, format=text, file="../../tests/KartikTalwar.test", section=bytecode)]
+fn compile(s: &str) -> Bytecode {
+    compile_text(s).unwrap()
+}
+
+mod vm {
+    use super::*;
+
+    // the VM must agree with the tree-walking interpreter
+    #[conformance::tests(exact, format=yaml, file="../../tests/KartikTalwar.test", section=variables)]
+    fn compile_and_execute(s: &str) -> Result<Variables, Box<dyn std::error::Error>> {
+        Ok(execute(&compile_text(s)?)?)
+    }
+}
+
+#[conformance::tests(exact, format=yaml, file="tests/errors.yaml.test")]
+fn evaluate(s: &str) -> Result<Variables, RuntimeError> {
+    let parse = tinyc_parser::parse_text(s);
+    tinyc_interpreter::run(&Program::cast(parse.syntax()).unwrap())
+}
+
+/// Compile errors, rendered as the CLI reports them.
+#[conformance::tests(exact, format=text, file="tests/diagnostics.test")]
+fn render(s: &str) -> String {
+    let parse = tinyc_parser::parse_text(s);
+    let error = tinyc_interpreter::compile(&Program::cast(parse.syntax()).unwrap()).unwrap_err();
+    let rendered =
+        Renderer::plain().render(&Diagnostic::from(&error), &SourceFile::new("test.c", s));
+    rendered.trim_end().to_string()
+}
@@ -0,0 +1,14 @@
+//! Runs the programs in `tests/programs`, checking the variables they end with.
+
+use {
+    conformance::{format::Yaml, runner::Runner},
+    tinyc_interpreter::{run_text, Variables},
+};
+
+fn run(s: &str) -> Variables {
+    run_text(s).unwrap()
+}
+
+fn main() {
+    Runner::new().exact("tests/programs", Yaml, run).run()
+}
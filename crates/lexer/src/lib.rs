@@ -4,7 +4,58 @@
 //
 // For the "canonical" Tiny-C lexer, see <https://gist.github.com/KartikTalwar/3095780>
 
-pub use tinyc_grammar::{TokenKind, Token};
+pub use {
+    crate::line_index::{ColumnEncoding, LineCol, LineIndex},
+    tinyc_grammar::{Token, TokenKind},
+};
+
+mod line_index;
+
+/// A token along with its byte offsets into the source text.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SpannedToken {
+    pub kind: TokenKind,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl SpannedToken {
+    pub fn len(self) -> u32 {
+        self.end - self.start
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn range(self) -> std::ops::Range<usize> {
+        self.start as usize..self.end as usize
+    }
+}
+
+impl From<SpannedToken> for Token {
+    fn from(token: SpannedToken) -> Token {
+        Token {
+            kind: token.kind,
+            len: token.len(),
+        }
+    }
+}
+
+/// Like `tokenize`, but tracks where each token starts and ends in `source`.
+pub fn tokenize_spanned(source: &str) -> impl Iterator<Item = SpannedToken> + '_ {
+    let mut offset = 0;
+    tokenize(source).map(move |token| {
+        let start = offset;
+        offset += token.len;
+        SpannedToken {
+            kind: token.kind,
+            start,
+            end: offset,
+        }
+    })
+}
 
 pub fn tokenize(mut source: &str) -> impl Iterator<Item = Token> + '_ {
     std::iter::from_fn(move || {
@@ -24,15 +75,39 @@ pub fn lex(source: &str) -> Token {
             kind: TokenKind::Whitespace,
             len: source
                 .find(is_not_whitespace)
-                .unwrap_or_else(|| source.len()) as u32,
+                .unwrap_or(source.len()) as u32,
+        }
+    } else if source.starts_with("//") {
+        let len = match source.find('\n') {
+            // the `\r` of a `\r\n` line ending is not part of the comment either
+            Some(end) => source[..end]
+                .strip_suffix('\r')
+                .unwrap_or(&source[..end])
+                .len(),
+            None => source.len(),
+        };
+        Token {
+            kind: TokenKind::LineComment,
+            len: len as u32,
+        }
+    } else if let Some(comment) = source.strip_prefix("/*") {
+        match comment.find("*/") {
+            Some(end) => Token {
+                kind: TokenKind::BlockComment,
+                len: (end + 4) as u32,
+            },
+            None => Token {
+                kind: TokenKind::UnterminatedBlockComment,
+                len: source.len() as u32,
+            },
         }
     } else if source.starts_with(is_digit) {
         Token {
             kind: TokenKind::Integer,
-            len: source.find(is_not_digit).unwrap_or_else(|| source.len()) as u32,
+            len: source.find(is_not_digit).unwrap_or(source.len()) as u32,
         }
     } else if source.starts_with(is_ident) {
-        let len = source.find(is_not_ident).unwrap_or_else(|| source.len());
+        let len = source.find(is_not_ident).unwrap_or(source.len());
         Token {
             kind: TokenKind::from_identifier(&source[..len]),
             len: len as u32,
@@ -74,7 +149,7 @@ fn is_not_digit(c: char) -> bool {
 }
 
 fn is_ident(c: char) -> bool {
-    'a' <= c && c <= 'z'
+    c.is_ascii_lowercase()
 }
 
 fn is_not_ident(c: char) -> bool {
@@ -0,0 +1,299 @@
+use std::{collections::HashMap, fmt, ops::Range};
+
+/// Maps byte offsets in some source text to line/column positions and back.
+///
+/// Lines are terminated by `\n`; a `\r\n` pair is treated as one terminator,
+/// so the `\r` never shows up as part of a line's content.
+/// Columns can be counted in either UTF-8 bytes or UTF-16 code units
+/// (the latter being what e.g. the Language Server Protocol speaks).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LineIndex {
+    /// Offset of the start of each line. Always starts with `0`.
+    line_starts: Vec<u32>,
+    /// Offset of the end of each line's content, before the line terminator.
+    line_ends: Vec<u32>,
+    /// Characters that are more than one byte wide, per line.
+    wide_chars: HashMap<u32, Vec<WideChar>>,
+}
+
+/// A zero-based line and column position.
+///
+/// `Display` prints the conventional one-based `line:col` form.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct LineCol {
+    pub line: u32,
+    pub col: u32,
+}
+
+/// The unit in which columns are counted.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ColumnEncoding {
+    /// Columns count UTF-8 bytes.
+    Utf8,
+    /// Columns count UTF-16 code units.
+    Utf16,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+struct WideChar {
+    /// Start column of the character, in UTF-8 bytes.
+    start: u32,
+    /// End column of the character, in UTF-8 bytes.
+    end: u32,
+}
+
+impl WideChar {
+    fn len_utf8(self) -> u32 {
+        self.end - self.start
+    }
+
+    fn len_utf16(self) -> u32 {
+        if self.len_utf8() == 4 {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+impl fmt::Display for LineCol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line + 1, self.col + 1)
+    }
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> LineIndex {
+        let mut line_starts = vec![0];
+        let mut line_ends = vec![];
+        let mut wide_chars = HashMap::new();
+        let mut line_wide_chars = vec![];
+
+        let mut line = 0;
+        let mut line_start = 0;
+        let mut prev = None;
+        for (offset, c) in text.char_indices() {
+            let offset = offset as u32;
+            if c == '\n' {
+                let end = if prev == Some('\r') { offset - 1 } else { offset };
+                line_ends.push(end);
+                line_starts.push(offset + 1);
+                if !line_wide_chars.is_empty() {
+                    wide_chars.insert(line, std::mem::take(&mut line_wide_chars));
+                }
+                line += 1;
+                line_start = offset + 1;
+            } else if c.len_utf8() > 1 {
+                let start = offset - line_start;
+                line_wide_chars.push(WideChar {
+                    start,
+                    end: start + c.len_utf8() as u32,
+                });
+            }
+            prev = Some(c);
+        }
+        line_ends.push(text.len() as u32);
+        if !line_wide_chars.is_empty() {
+            wide_chars.insert(line, line_wide_chars);
+        }
+
+        LineIndex {
+            line_starts,
+            line_ends,
+            wide_chars,
+        }
+    }
+
+    /// The number of lines in the text.
+    /// Text ending in a line terminator has an empty last line.
+    pub fn line_count(&self) -> u32 {
+        self.line_starts.len() as u32
+    }
+
+    /// The byte range of `line`'s content, excluding its line terminator.
+    pub fn line_range(&self, line: u32) -> Option<Range<u32>> {
+        let start = *self.line_starts.get(line as usize)?;
+        let end = self.line_ends[line as usize];
+        Some(start..end)
+    }
+
+    /// The position of a byte offset, with columns in UTF-8 bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is past the end of the text.
+    pub fn line_col(&self, offset: u32) -> LineCol {
+        let end = *self.line_ends.last().unwrap();
+        assert!(offset <= end, "offset {} is past the end of the text ({})", offset, end);
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        };
+        LineCol {
+            line: line as u32,
+            col: offset - self.line_starts[line],
+        }
+    }
+
+    /// The position of a byte offset, with columns in the given encoding.
+    pub fn line_col_in(&self, offset: u32, encoding: ColumnEncoding) -> LineCol {
+        let pos = self.line_col(offset);
+        match encoding {
+            ColumnEncoding::Utf8 => pos,
+            ColumnEncoding::Utf16 => self.to_utf16(pos),
+        }
+    }
+
+    /// The byte offset of a position with columns in UTF-8 bytes,
+    /// or `None` if the position is outside the text.
+    ///
+    /// Columns past the end of the line's content are clamped to it,
+    /// and columns in the middle of a character are moved back to its start.
+    pub fn offset(&self, pos: LineCol) -> Option<u32> {
+        let range = self.line_range(pos.line)?;
+        let mut col = u32::min(pos.col, range.end - range.start);
+        if let Some(c) = self
+            .wide_chars_on(pos.line)
+            .find(|c| c.start < col && col < c.end)
+        {
+            col = c.start;
+        }
+        Some(range.start + col)
+    }
+
+    /// The byte offset of a position with columns in the given encoding,
+    /// or `None` if the position is outside the text.
+    pub fn offset_in(&self, pos: LineCol, encoding: ColumnEncoding) -> Option<u32> {
+        match encoding {
+            ColumnEncoding::Utf8 => self.offset(pos),
+            ColumnEncoding::Utf16 => self.offset(self.to_utf8(pos)),
+        }
+    }
+
+    /// Convert a position with UTF-8 columns to one with UTF-16 columns.
+    pub fn to_utf16(&self, pos: LineCol) -> LineCol {
+        let mut col = pos.col;
+        for c in self.wide_chars_on(pos.line) {
+            if c.end <= pos.col {
+                col -= c.len_utf8() - c.len_utf16();
+            }
+        }
+        LineCol { line: pos.line, col }
+    }
+
+    /// Convert a position with UTF-16 columns to one with UTF-8 columns.
+    ///
+    /// A column between the two halves of a surrogate pair
+    /// is moved back to the start of its character.
+    pub fn to_utf8(&self, pos: LineCol) -> LineCol {
+        let mut col = pos.col;
+        for c in self.wide_chars_on(pos.line) {
+            if c.start >= col {
+                break;
+            }
+            if col < c.start + c.len_utf16() {
+                col = c.start;
+                break;
+            }
+            col += c.len_utf8() - c.len_utf16();
+        }
+        LineCol { line: pos.line, col }
+    }
+
+    /// Convert a position with UTF-8 columns to one with columns counting characters,
+    /// as people (and their editors) count them.
+    pub fn to_chars(&self, pos: LineCol) -> LineCol {
+        let mut col = pos.col;
+        for c in self.wide_chars_on(pos.line) {
+            if c.end <= pos.col {
+                col -= c.len_utf8() - 1;
+            }
+        }
+        LineCol { line: pos.line, col }
+    }
+
+    fn wide_chars_on(&self, line: u32) -> impl Iterator<Item = WideChar> + '_ {
+        self.wide_chars.get(&line).into_iter().flatten().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ColumnEncoding::*, LineCol, LineIndex};
+
+    fn pos(line: u32, col: u32) -> LineCol {
+        LineCol { line, col }
+    }
+
+    #[test]
+    fn lines() {
+        let text = "a=1;\nb=2;\n\nc=3;";
+        let index = LineIndex::new(text);
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.line_col(0), pos(0, 0));
+        assert_eq!(index.line_col(4), pos(0, 4));
+        assert_eq!(index.line_col(5), pos(1, 0));
+        assert_eq!(index.line_col(10), pos(2, 0));
+        assert_eq!(index.line_col(14), pos(3, 3));
+        assert_eq!(index.line_col(15), pos(3, 4));
+        assert_eq!(index.line_range(1), Some(5..9));
+        assert_eq!(index.line_range(2), Some(10..10));
+        assert_eq!(index.line_range(4), None);
+        for offset in 0..=text.len() as u32 {
+            assert_eq!(index.offset(index.line_col(offset)), Some(offset));
+        }
+    }
+
+    #[test]
+    fn crlf() {
+        let text = "a=1;\r\nb=2;\r\n";
+        let index = LineIndex::new(text);
+        assert_eq!(index.line_count(), 3);
+        assert_eq!(index.line_range(0), Some(0..4));
+        assert_eq!(index.line_range(1), Some(6..10));
+        assert_eq!(index.line_range(2), Some(12..12));
+        assert_eq!(index.line_col(6), pos(1, 0));
+        assert_eq!(index.offset(pos(0, 100)), Some(4));
+        assert_eq!(pos(1, 0).to_string(), "2:1");
+    }
+
+    #[test]
+    fn utf16() {
+        // é is 2 UTF-8 bytes and 1 UTF-16 unit; 𝔵 is 4 UTF-8 bytes and 2 UTF-16 units
+        let text = "x;\né=𝔵;";
+        let index = LineIndex::new(text);
+        let semi = text.rfind(';').unwrap() as u32;
+        assert_eq!(index.line_col(semi), pos(1, 7));
+        assert_eq!(index.line_col_in(semi, Utf16), pos(1, 4));
+        assert_eq!(index.to_chars(pos(1, 7)), pos(1, 3));
+        assert_eq!(index.line_col_in(1, Utf16), pos(0, 1));
+        assert_eq!(index.offset_in(pos(1, 4), Utf16), Some(semi));
+        assert_eq!(index.offset_in(pos(1, 1), Utf16), Some(5));
+        for (offset, _) in text.char_indices() {
+            let offset = offset as u32;
+            let utf16 = index.line_col_in(offset, Utf16);
+            assert_eq!(index.offset_in(utf16, Utf16), Some(offset));
+        }
+    }
+
+    #[test]
+    fn inside_char() {
+        let text = "x;\né=𝔵;";
+        let index = LineIndex::new(text);
+        let e = text.find('é').unwrap() as u32;
+        let x = text.find('𝔵').unwrap() as u32;
+        // between the bytes of é, or of 𝔵
+        assert_eq!(index.offset(pos(1, 1)), Some(e));
+        assert_eq!(index.offset(pos(1, 5)), Some(x));
+        // between the halves of the surrogate pair of 𝔵
+        assert_eq!(index.to_utf8(pos(1, 3)), pos(1, 3));
+        assert_eq!(index.offset_in(pos(1, 3), Utf16), Some(x));
+        for col in 0..10 {
+            for &encoding in &[Utf8, Utf16] {
+                let offset = index.offset_in(pos(1, col), encoding).unwrap();
+                assert!(text.is_char_boundary(offset as usize));
+            }
+        }
+    }
+}
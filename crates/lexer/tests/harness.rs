@@ -1,23 +1,14 @@
-use {
-    conformance, json, ron,
-    tinyc_lexer::{tokenize, Token},
-    yaml,
-};
+use tinyc_lexer::{tokenize, tokenize_spanned, SpannedToken, Token};
 
-mod ron_pretty {
-    use serde::Serialize;
-
-    pub fn to_string<T>(value: &T) -> ron::ser::Result<String>
-    where
-        T: Serialize,
-    {
-        ron::ser::to_string_pretty(value, Default::default())
-    }
-}
-
-#[conformance::tests(exact, serde=yaml, file="tests/KartikTalwar.yaml.test")]
-#[conformance::tests(exact, ser=ron_pretty::to_string, de=ron::de::from_str, file="tests/KartikTalwar.ron.test")]
-#[conformance::tests(exact, serde=json, ser=json::to_string_pretty, file="tests/KartikTalwar.json.test")]
+#[conformance::tests(exact, format=yaml, file="../../tests/KartikTalwar.test", section=tokens)]
+#[conformance::tests(exact, format=yaml, file="tests/formats.test", section=yaml)]
+#[conformance::tests(exact, format=ron_pretty, value=Vec<Token>, file="tests/formats.test", section=ron)]
+#[conformance::tests(exact, format=json_pretty, file="tests/formats.test", section=json)]
 fn lex_tokens(s: &str) -> Vec<Token> {
     tokenize(s).collect()
 }
+
+#[conformance::tests(exact, format=yaml, file="tests/spans.yaml.test")]
+fn lex_spans(s: &str) -> Vec<SpannedToken> {
+    tokenize_spanned(s).collect()
+}
@@ -0,0 +1,87 @@
+use {
+    lsp_types::{Position, Range, TextDocumentContentChangeEvent},
+    tinyc_grammar::{TextRange, TextSize},
+    tinyc_lexer::{ColumnEncoding, LineCol, LineIndex},
+    tinyc_parser::{parse_text, Parse, TextEdit},
+};
+
+/// An open text document and its syntax tree.
+#[derive(Debug, Clone)]
+pub struct Document {
+    text: String,
+    parse: Parse,
+    line_index: LineIndex,
+    encoding: ColumnEncoding,
+}
+
+impl Document {
+    /// A document whose positions have columns in `encoding`.
+    pub fn new(text: String, encoding: ColumnEncoding) -> Self {
+        Document {
+            parse: parse_text(&text),
+            line_index: LineIndex::new(&text),
+            text,
+            encoding,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn parse(&self) -> &Parse {
+        &self.parse
+    }
+
+    /// Apply a change from the client, reparsing incrementally if it is to a range.
+    pub fn apply_change(&mut self, change: TextDocumentContentChangeEvent) {
+        match change.range {
+            Some(range) => {
+                let edit = TextEdit::replace(self.text_range(range), change.text);
+                self.parse = self.parse.reparse(&edit);
+                self.text = edit.apply(&self.text);
+            }
+            None => {
+                self.parse = parse_text(&change.text);
+                self.text = change.text;
+            }
+        }
+        self.line_index = LineIndex::new(&self.text);
+    }
+
+    /// The byte offset of a client position.
+    ///
+    /// Positions past the end of a line or of the text are clamped to it,
+    /// and positions in the middle of a character are moved back to its start.
+    pub fn offset(&self, position: Position) -> TextSize {
+        let pos = LineCol {
+            line: position.line,
+            col: position.character,
+        };
+        let offset = self
+            .line_index
+            .offset_in(pos, self.encoding)
+            .unwrap_or(self.text.len() as u32);
+        offset.into()
+    }
+
+    /// The client position of a byte offset.
+    pub fn position(&self, offset: TextSize) -> Position {
+        let pos = self.line_index.line_col_in(offset.into(), self.encoding);
+        Position::new(pos.line, pos.col)
+    }
+
+    /// The byte range of a client range.
+    ///
+    /// A range that ends before it starts is taken the other way around.
+    pub fn text_range(&self, range: Range) -> TextRange {
+        let start = self.offset(range.start);
+        let end = self.offset(range.end);
+        TextRange::new(start.min(end), start.max(end))
+    }
+
+    /// The client range of a byte range.
+    pub fn range(&self, range: TextRange) -> Range {
+        Range::new(self.position(range.start()), self.position(range.end()))
+    }
+}
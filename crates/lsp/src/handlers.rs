@@ -0,0 +1,203 @@
+//! The language features, computed from a `Document`.
+
+use {
+    crate::document::Document,
+    lsp_types::{
+        Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, DocumentSymbol, FoldingRange,
+        Hover, HoverContents, Location, MarkupContent, MarkupKind, NumberOrString, Position,
+        SemanticToken, SemanticTokenType, SemanticTokens, SemanticTokensLegend, SymbolKind, Uri,
+    },
+    std::collections::HashSet,
+    tinyc_diagnostics::{parse_diagnostics, Severity},
+    tinyc_grammar::{syntax::SyntaxToken, SyntaxKind, TextRange, TokenKind},
+};
+
+/// The semantic token types, in the order of their indices in the legend.
+const TOKEN_TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::OPERATOR,
+];
+
+pub fn semantic_tokens_legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: vec![],
+    }
+}
+
+/// The index of a token's semantic token type in the legend, if it has one.
+fn token_type(kind: TokenKind) -> Option<u32> {
+    let ty = if kind.is_keyword() {
+        SemanticTokenType::KEYWORD
+    } else if kind == TokenKind::Identifier {
+        SemanticTokenType::VARIABLE
+    } else if kind.is_literal() {
+        SemanticTokenType::NUMBER
+    } else if kind.is_punctuation() {
+        SemanticTokenType::OPERATOR
+    } else {
+        return None;
+    };
+    TOKEN_TYPES
+        .iter()
+        .position(|it| *it == ty)
+        .map(|i| i as u32)
+}
+
+fn tokens(doc: &Document) -> impl Iterator<Item = SyntaxToken> {
+    doc.parse()
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+}
+
+/// Parse and lexical errors, converted to LSP diagnostics.
+///
+/// Notes and help are appended to the message;
+/// secondary labels become related information.
+pub fn diagnostics(doc: &Document, uri: &Uri) -> Vec<Diagnostic> {
+    parse_diagnostics(doc.parse())
+        .into_iter()
+        .map(|diagnostic| {
+            let range = diagnostic
+                .range()
+                .unwrap_or_else(|| TextRange::empty(0.into()));
+            let mut message = diagnostic.message.clone();
+            for note in &diagnostic.notes {
+                message.push_str(&format!("\nnote: {}", note));
+            }
+            for help in &diagnostic.help {
+                message.push_str(&format!("\nhelp: {}", help));
+            }
+            let related: Vec<_> = diagnostic
+                .labels
+                .iter()
+                .filter(|label| !label.primary)
+                .map(|label| DiagnosticRelatedInformation {
+                    location: Location::new(uri.clone(), doc.range(label.range)),
+                    message: label.message.clone(),
+                })
+                .collect();
+            Diagnostic {
+                range: doc.range(range),
+                severity: Some(match diagnostic.severity {
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                    Severity::Error => DiagnosticSeverity::ERROR,
+                }),
+                code: diagnostic
+                    .code
+                    .map(|code| NumberOrString::String(code.to_string())),
+                source: Some("tinyc".to_string()),
+                message,
+                related_information: if related.is_empty() {
+                    None
+                } else {
+                    Some(related)
+                },
+                ..Diagnostic::default()
+            }
+        })
+        .collect()
+}
+
+/// Keywords, identifiers, integers and operators, delta-encoded.
+///
+/// None of these tokens can span multiple lines.
+pub fn semantic_tokens(doc: &Document) -> SemanticTokens {
+    let mut data = vec![];
+    let mut prev = Position::new(0, 0);
+    for token in tokens(doc) {
+        let ty = match token.kind().as_token().and_then(token_type) {
+            Some(ty) => ty,
+            None => continue,
+        };
+        let range = doc.range(token.text_range());
+        let start = range.start;
+        data.push(SemanticToken {
+            delta_line: start.line - prev.line,
+            delta_start: if start.line == prev.line {
+                start.character - prev.character
+            } else {
+                start.character
+            },
+            length: range.end.character - start.character,
+            token_type: ty,
+            token_modifiers_bitset: 0,
+        });
+        prev = start;
+    }
+    SemanticTokens {
+        result_id: None,
+        data,
+    }
+}
+
+/// One symbol per variable, at its first use.
+pub fn document_symbols(doc: &Document) -> Vec<DocumentSymbol> {
+    let mut seen = HashSet::new();
+    tokens(doc)
+        .filter(|token| token.kind() == SyntaxKind::Identifier)
+        .filter(|token| seen.insert(token.text().to_string()))
+        .map(|token| {
+            let range = doc.range(token.text_range());
+            #[allow(deprecated)]
+            DocumentSymbol {
+                name: token.text().to_string(),
+                detail: None,
+                kind: SymbolKind::VARIABLE,
+                tags: None,
+                deprecated: None,
+                range,
+                selection_range: range,
+                children: None,
+            }
+        })
+        .collect()
+}
+
+/// Blocks that span multiple lines.
+pub fn folding_ranges(doc: &Document) -> Vec<FoldingRange> {
+    doc.parse()
+        .syntax()
+        .descendants()
+        .filter(|node| node.kind() == SyntaxKind::StatementBlock)
+        .map(|node| doc.range(node.text_range()))
+        .filter(|range| range.start.line < range.end.line)
+        .map(|range| FoldingRange {
+            start_line: range.start.line,
+            start_character: Some(range.start.character),
+            end_line: range.end.line,
+            end_character: Some(range.end.character),
+            kind: None,
+            collapsed_text: None,
+        })
+        .collect()
+}
+
+/// The path of syntax kinds from the root to the token at `position`.
+pub fn hover(doc: &Document, position: Position) -> Option<Hover> {
+    let offset = doc.offset(position);
+    let root = doc.parse().syntax();
+    if !root.text_range().contains_inclusive(offset) {
+        return None;
+    }
+    // Between two tokens, prefer the one that is not trivia.
+    let token = root
+        .token_at_offset(offset)
+        .max_by_key(|token| !token.kind().as_token().is_some_and(TokenKind::is_trivia))?;
+    let mut path: Vec<_> = token
+        .parent_ancestors()
+        .map(|node| node.kind().name())
+        .collect();
+    path.reverse();
+    path.push(token.kind().name());
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: format!("```text\n{}\n```", path.join(" > ")),
+        }),
+        range: Some(doc.range(token.text_range())),
+    })
+}
@@ -0,0 +1,292 @@
+//! A language server for Tiny-C.
+//!
+//! The server speaks the Language Server Protocol over any `lsp_server::Connection`;
+//! the `tinyc_lsp` binary runs it over stdio.
+//! Documents are kept parsed, and reparsed incrementally as the client edits them.
+//!
+//! Supported features:
+//!
+//! - diagnostics for lexical and syntax errors, published whenever a document changes
+//! - semantic tokens for keywords, identifiers, integers and operators
+//! - document symbols for variables
+//! - folding ranges for blocks
+//! - hover showing the path of syntax kinds to the token under the cursor
+
+pub use crate::document::Document;
+
+use {
+    lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response},
+    lsp_types::{
+        notification::{
+            DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, LogMessage,
+            Notification as _, PublishDiagnostics,
+        },
+        request::{
+            DocumentSymbolRequest, FoldingRangeRequest, HoverRequest, Request as _,
+            SemanticTokensFullRequest,
+        },
+        DocumentSymbolResponse, FoldingRangeProviderCapability, HoverProviderCapability,
+        InitializeParams, InitializeResult, LogMessageParams, MessageType, OneOf,
+        PositionEncodingKind, PublishDiagnosticsParams, SemanticTokensFullOptions,
+        SemanticTokensOptions, SemanticTokensResult, SemanticTokensServerCapabilities,
+        ServerCapabilities, ServerInfo, TextDocumentSyncCapability, TextDocumentSyncKind, Uri,
+    },
+    serde::{de::DeserializeOwned, Serialize},
+    std::{collections::HashMap, error::Error},
+    tinyc_lexer::ColumnEncoding,
+};
+
+mod document;
+pub mod handlers;
+
+/// Run the server on `connection` until the client asks it to exit.
+///
+/// This does the initialization handshake, then serves requests.
+pub fn run(connection: &Connection) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (id, params) = connection.initialize_start()?;
+    let params: InitializeParams = json::from_value(params)?;
+    let encoding = negotiate_encoding(&params);
+    let result = InitializeResult {
+        capabilities: capabilities(encoding),
+        server_info: Some(ServerInfo {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        }),
+    };
+    connection.initialize_finish(id, json::to_value(result)?)?;
+
+    let mut server = Server {
+        connection,
+        encoding,
+        documents: HashMap::new(),
+    };
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                server.on_request(request)?;
+            }
+            Message::Notification(notification) => server.on_notification(notification)?,
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Use UTF-8 columns if the client supports them, since that is what the tree uses,
+/// and otherwise the mandatory UTF-16.
+fn negotiate_encoding(params: &InitializeParams) -> ColumnEncoding {
+    let encodings = params
+        .capabilities
+        .general
+        .as_ref()
+        .and_then(|general| general.position_encodings.as_ref());
+    if encodings.is_some_and(|encodings| encodings.contains(&PositionEncodingKind::UTF8)) {
+        ColumnEncoding::Utf8
+    } else {
+        ColumnEncoding::Utf16
+    }
+}
+
+fn capabilities(encoding: ColumnEncoding) -> ServerCapabilities {
+    ServerCapabilities {
+        position_encoding: Some(match encoding {
+            ColumnEncoding::Utf8 => PositionEncodingKind::UTF8,
+            ColumnEncoding::Utf16 => PositionEncodingKind::UTF16,
+        }),
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::INCREMENTAL,
+        )),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: handlers::semantic_tokens_legend(),
+                full: Some(SemanticTokensFullOptions::Bool(true)),
+                ..SemanticTokensOptions::default()
+            },
+        )),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        ..ServerCapabilities::default()
+    }
+}
+
+struct Server<'a> {
+    connection: &'a Connection,
+    encoding: ColumnEncoding,
+    documents: HashMap<Uri, Document>,
+}
+
+type Result<T = (), E = Box<dyn Error + Send + Sync>> = std::result::Result<T, E>;
+
+impl Server<'_> {
+    fn send(&self, message: impl Into<Message>) -> Result {
+        self.connection.sender.send(message.into())?;
+        Ok(())
+    }
+
+    fn on_request(&mut self, request: Request) -> Result {
+        let response = match &*request.method {
+            SemanticTokensFullRequest::METHOD => self
+                .respond::<SemanticTokensFullRequest, _>(request, |doc, _| {
+                    Some(SemanticTokensResult::Tokens(handlers::semantic_tokens(doc)))
+                }),
+            DocumentSymbolRequest::METHOD => {
+                self.respond::<DocumentSymbolRequest, _>(request, |doc, _| {
+                    Some(DocumentSymbolResponse::Nested(handlers::document_symbols(
+                        doc,
+                    )))
+                })
+            }
+            FoldingRangeRequest::METHOD => self
+                .respond::<FoldingRangeRequest, _>(request, |doc, _| {
+                    Some(handlers::folding_ranges(doc))
+                }),
+            HoverRequest::METHOD => self.respond::<HoverRequest, _>(request, |doc, params| {
+                handlers::hover(doc, params.text_document_position_params.position)
+            }),
+            _ => Response::new_err(
+                request.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unknown request `{}`", request.method),
+            ),
+        };
+        self.send(response)
+    }
+
+    /// Answer a request about a document with `f`.
+    fn respond<R, F>(&self, request: Request, f: F) -> Response
+    where
+        R: lsp_types::request::Request,
+        R::Params: DocumentParams,
+        F: FnOnce(&Document, &R::Params) -> R::Result,
+    {
+        let (id, params) = match extract::<R::Params>(request.id, request.params) {
+            Ok(it) => it,
+            Err(response) => return response,
+        };
+        match self.documents.get(params.uri()) {
+            Some(doc) => Response::new_ok(id, f(doc, &params)),
+            None => Response::new_err(
+                id,
+                ErrorCode::InvalidParams as i32,
+                format!("unknown document `{}`", params.uri().as_str()),
+            ),
+        }
+    }
+
+    fn on_notification(&mut self, notification: Notification) -> Result {
+        match &*notification.method {
+            DidOpenTextDocument::METHOD => {
+                self.notified::<DidOpenTextDocument, _>(notification, |server, params| {
+                    let doc = Document::new(params.text_document.text, server.encoding);
+                    server
+                        .documents
+                        .insert(params.text_document.uri.clone(), doc);
+                    server.publish_diagnostics(params.text_document.uri)
+                })
+            }
+            DidChangeTextDocument::METHOD => {
+                self.notified::<DidChangeTextDocument, _>(notification, |server, params| {
+                    let uri = params.text_document.uri;
+                    if let Some(doc) = server.documents.get_mut(&uri) {
+                        for change in params.content_changes {
+                            doc.apply_change(change);
+                        }
+                        server.publish_diagnostics(uri)?;
+                    }
+                    Ok(())
+                })
+            }
+            DidCloseTextDocument::METHOD => {
+                self.notified::<DidCloseTextDocument, _>(notification, |server, params| {
+                    server.documents.remove(&params.text_document.uri);
+                    // Clear the diagnostics of the closed document.
+                    server.notify::<PublishDiagnostics>(PublishDiagnosticsParams::new(
+                        params.text_document.uri,
+                        vec![],
+                        None,
+                    ))
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Handle a notification with `f`.
+    ///
+    /// There is no response to report invalid parameters in,
+    /// so they are logged to the client and the notification is ignored.
+    fn notified<N, F>(&mut self, notification: Notification, f: F) -> Result
+    where
+        N: lsp_types::notification::Notification,
+        F: FnOnce(&mut Self, N::Params) -> Result,
+    {
+        match json::from_value(notification.params) {
+            Ok(params) => f(self, params),
+            Err(error) => self.notify::<LogMessage>(LogMessageParams {
+                typ: MessageType::ERROR,
+                message: format!("invalid `{}` notification: {}", N::METHOD, error),
+            }),
+        }
+    }
+
+    fn publish_diagnostics(&self, uri: Uri) -> Result {
+        let diagnostics = handlers::diagnostics(&self.documents[&uri], &uri);
+        self.notify::<PublishDiagnostics>(PublishDiagnosticsParams::new(uri, diagnostics, None))
+    }
+
+    fn notify<N>(&self, params: N::Params) -> Result
+    where
+        N: lsp_types::notification::Notification,
+        N::Params: Serialize,
+    {
+        self.send(Notification::new(N::METHOD.to_string(), params))
+    }
+}
+
+/// Deserialize request parameters, or produce the error response if they are invalid.
+fn extract<P: DeserializeOwned>(
+    id: RequestId,
+    params: json::Value,
+) -> Result<(RequestId, P), Response> {
+    match json::from_value(params) {
+        Ok(params) => Ok((id, params)),
+        Err(error) => Err(Response::new_err(
+            id,
+            ErrorCode::InvalidParams as i32,
+            error.to_string(),
+        )),
+    }
+}
+
+/// Request parameters that are about a document.
+trait DocumentParams: DeserializeOwned {
+    fn uri(&self) -> &Uri;
+}
+
+impl DocumentParams for lsp_types::SemanticTokensParams {
+    fn uri(&self) -> &Uri {
+        &self.text_document.uri
+    }
+}
+
+impl DocumentParams for lsp_types::DocumentSymbolParams {
+    fn uri(&self) -> &Uri {
+        &self.text_document.uri
+    }
+}
+
+impl DocumentParams for lsp_types::FoldingRangeParams {
+    fn uri(&self) -> &Uri {
+        &self.text_document.uri
+    }
+}
+
+impl DocumentParams for lsp_types::HoverParams {
+    fn uri(&self) -> &Uri {
+        &self.text_document_position_params.text_document.uri
+    }
+}
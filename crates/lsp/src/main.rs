@@ -0,0 +1,11 @@
+//! Run the Tiny-C language server over stdio.
+
+use {lsp_server::Connection, std::error::Error};
+
+fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (connection, io_threads) = Connection::stdio();
+    tinyc_lsp::run(&connection)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
@@ -0,0 +1,326 @@
+//! Drive the server binary over stdio with a scripted client.
+
+use {
+    json::{json, Value},
+    lsp_server::{Message, Notification, Request, RequestId},
+    std::{
+        io::{BufReader, Write},
+        process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    },
+};
+
+const URI: &str = "file:///test.tc";
+
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: i32,
+}
+
+impl Client {
+    /// Start the server and initialize it.
+    fn start(capabilities: Value) -> (Client, Value) {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_tinyc_lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut client = Client {
+            stdin: child.stdin.take().unwrap(),
+            stdout: BufReader::new(child.stdout.take().unwrap()),
+            child,
+            next_id: 0,
+        };
+        let result = client.request("initialize", json!({ "capabilities": capabilities }));
+        client.notify("initialized", json!({}));
+        (client, result)
+    }
+
+    fn send(&mut self, message: Message) {
+        message.write(&mut self.stdin).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Message {
+        Message::read(&mut self.stdout).unwrap().unwrap()
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = RequestId::from(self.next_id);
+        self.send(Request::new(id.clone(), method.to_string(), params).into());
+        loop {
+            match self.receive() {
+                Message::Response(response) if response.id == id => {
+                    return response.response_result.unwrap();
+                }
+                Message::Notification(_) => {}
+                message => panic!("unexpected message: {:?}", message),
+            }
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(Notification::new(method.to_string(), params).into());
+    }
+
+    /// Wait for the next notification with this method and return its params.
+    fn notification(&mut self, method: &str) -> Value {
+        loop {
+            match self.receive() {
+                Message::Notification(notification) if notification.method == method => {
+                    return notification.params;
+                }
+                Message::Notification(_) => {}
+                message => panic!("unexpected message: {:?}", message),
+            }
+        }
+    }
+
+    fn open(&mut self, text: &str) -> Value {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": URI, "languageId": "tinyc", "version": 1, "text": text },
+            }),
+        );
+        self.notification("textDocument/publishDiagnostics")
+    }
+
+    fn change(&mut self, version: i32, changes: Value) -> Value {
+        self.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": URI, "version": version },
+                "contentChanges": changes,
+            }),
+        );
+        self.notification("textDocument/publishDiagnostics")
+    }
+
+    fn document_request(&mut self, method: &str, params: Value) -> Value {
+        let mut params = params;
+        params["textDocument"] = json!({ "uri": URI });
+        self.request(method, params)
+    }
+
+    fn shutdown(mut self) {
+        assert_eq!(self.request("shutdown", Value::Null), Value::Null);
+        self.notify("exit", Value::Null);
+        drop(self.stdin);
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+fn range(start: (u32, u32), end: (u32, u32)) -> Value {
+    json!({
+        "start": { "line": start.0, "character": start.1 },
+        "end": { "line": end.0, "character": end.1 },
+    })
+}
+
+#[test]
+fn initialize() {
+    let (client, result) = Client::start(json!({}));
+    let capabilities = &result["capabilities"];
+    assert_eq!(capabilities["positionEncoding"], "utf-16");
+    assert_eq!(capabilities["textDocumentSync"], 2);
+    assert_eq!(
+        capabilities["semanticTokensProvider"]["legend"]["tokenTypes"],
+        json!(["keyword", "variable", "number", "operator"]),
+    );
+    assert_eq!(result["serverInfo"]["name"], "tinyc_lsp");
+    client.shutdown();
+
+    let (client, result) = Client::start(json!({
+        "general": { "positionEncodings": ["utf-8", "utf-16"] },
+    }));
+    assert_eq!(result["capabilities"]["positionEncoding"], "utf-8");
+    client.shutdown();
+}
+
+#[test]
+fn diagnostics() {
+    let (mut client, _) = Client::start(json!({}));
+    let published = client.open("a = 1;\nif a < 2 b = 3;\n");
+    assert_eq!(published["uri"], URI);
+    let diagnostics = published["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0]["range"], range((1, 3), (1, 4)));
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(diagnostics[0]["code"], "E0001");
+    assert_eq!(diagnostics[0]["source"], "tinyc");
+    assert_eq!(
+        diagnostics[0]["message"],
+        "expected `(`, found identifier\n\
+         help: the conditions of `if` and `while` must be in parentheses",
+    );
+
+    // Fix the condition with two edits.
+    let published = client.change(
+        2,
+        json!([
+            { "range": range((1, 3), (1, 3)), "text": "(" },
+            { "range": range((1, 9), (1, 9)), "text": ")" },
+        ]),
+    );
+    assert_eq!(published["diagnostics"], json!([]));
+
+    // Replace the whole text.
+    let published = client.change(3, json!([{ "text": "a = Z;" }]));
+    let diagnostics = published["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["range"], range((0, 4), (0, 5)));
+
+    client.notify(
+        "textDocument/didClose",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    let published = client.notification("textDocument/publishDiagnostics");
+    assert_eq!(published["diagnostics"], json!([]));
+    client.shutdown();
+}
+
+#[test]
+fn malformed_changes() {
+    let (mut client, _) = Client::start(json!({}));
+    // 𝔵 is two UTF-16 code units, at columns 10 and 11.
+    client.open("a = 1; /* 𝔵 */");
+
+    // An edit between the two halves of 𝔵 goes before it.
+    let published = client.change(
+        2,
+        json!([{ "range": range((0, 11), (0, 11)), "text": "b" }]),
+    );
+    assert_eq!(published["diagnostics"], json!([]));
+
+    // An inverted range is taken the other way around.
+    let published = client.change(
+        3,
+        json!([{ "range": range((0, 6), (0, 0)), "text": "c = 2;" }]),
+    );
+    assert_eq!(published["diagnostics"], json!([]));
+
+    let result = client.document_request("textDocument/documentSymbol", json!({}));
+    assert_eq!(result[0]["name"], "c");
+    let result = client.document_request("textDocument/semanticTokens/full", json!({}));
+    // the comment is not a semantic token, so this checks where it ends
+    assert_eq!(result["data"].as_array().unwrap().len(), 4 * 5);
+    client.shutdown();
+}
+
+#[test]
+fn malformed_notifications() {
+    let (mut client, _) = Client::start(json!({}));
+    client.open("a = 1;");
+    for method in &[
+        "textDocument/didOpen",
+        "textDocument/didChange",
+        "textDocument/didClose",
+    ] {
+        client.notify(method, json!({ "textDocument": 1 }));
+        let logged = client.notification("window/logMessage");
+        assert_eq!(logged["type"], 1);
+        let message = logged["message"].as_str().unwrap();
+        assert!(message.starts_with(&format!("invalid `{}` notification: ", method)));
+    }
+
+    // The server is still serving, and still has the document.
+    let result = client.document_request("textDocument/documentSymbol", json!({}));
+    assert_eq!(result[0]["name"], "a");
+    client.shutdown();
+}
+
+#[test]
+fn semantic_tokens() {
+    let (mut client, _) = Client::start(json!({}));
+    client.open("while (i < 10)\n  i = i + 1; // loop");
+    let result = client.document_request("textDocument/semanticTokens/full", json!({}));
+    #[rustfmt::skip]
+    let expected = json!([
+        // line, start, length, type, modifiers
+        0, 0, 5, 0, 0, // while
+        0, 6, 1, 3, 0, // (
+        0, 1, 1, 1, 0, // i
+        0, 2, 1, 3, 0, // <
+        0, 2, 2, 2, 0, // 10
+        0, 2, 1, 3, 0, // )
+        1, 2, 1, 1, 0, // i
+        0, 2, 1, 3, 0, // =
+        0, 2, 1, 1, 0, // i
+        0, 2, 1, 3, 0, // +
+        0, 2, 1, 2, 0, // 1
+        0, 1, 1, 3, 0, // ;
+    ]);
+    assert_eq!(result["data"], expected);
+    client.shutdown();
+}
+
+#[test]
+fn document_symbols() {
+    let (mut client, _) = Client::start(json!({}));
+    client.open("a = 1;\nb = a + c;\na = b;");
+    let result = client.document_request("textDocument/documentSymbol", json!({}));
+    let symbols: Vec<_> = result
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| {
+            assert_eq!(symbol["kind"], 13);
+            (symbol["name"].clone(), symbol["selectionRange"].clone())
+        })
+        .collect();
+    assert_eq!(
+        symbols,
+        [
+            (json!("a"), range((0, 0), (0, 1))),
+            (json!("b"), range((1, 0), (1, 1))),
+            (json!("c"), range((1, 8), (1, 9))),
+        ],
+    );
+    client.shutdown();
+}
+
+#[test]
+fn folding_ranges() {
+    let (mut client, _) = Client::start(json!({}));
+    client.open("{ a = 1; }\nwhile (a < 5) {\n  {\n    a = a + 1;\n  }\n}\n");
+    let result = client.document_request("textDocument/foldingRange", json!({}));
+    let ranges: Vec<_> = result
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|range| (range["startLine"].clone(), range["endLine"].clone()))
+        .collect();
+    assert_eq!(ranges, [(json!(1), json!(5)), (json!(2), json!(4))]);
+    client.shutdown();
+}
+
+#[test]
+fn hover() {
+    let (mut client, _) = Client::start(json!({}));
+    // The é makes UTF-16 and UTF-8 columns differ after it.
+    client.open("/* é */ a = 1;");
+    let result = client.document_request(
+        "textDocument/hover",
+        json!({ "position": { "line": 0, "character": 8 } }),
+    );
+    assert_eq!(
+        result["contents"]["value"],
+        "```text\nProgram > StatementExpression > ExpressionAssignment > Identifier\n```",
+    );
+    assert_eq!(result["range"], range((0, 8), (0, 9)));
+
+    // At the boundary between whitespace and a token, the token wins.
+    let result = client.document_request(
+        "textDocument/hover",
+        json!({ "position": { "line": 0, "character": 12 } }),
+    );
+    assert_eq!(
+        result["contents"]["value"],
+        "```text\nProgram > StatementExpression > ExpressionAssignment > ExpressionTerm \
+         > TermInteger > Integer\n```",
+    );
+    client.shutdown();
+}
@@ -0,0 +1,101 @@
+use {
+    crate::{TokenKind, TokenSet},
+    std::fmt,
+    tinyc_grammar::TextRange,
+};
+
+/// An error encountered while parsing.
+#[derive(serde::Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    /// What kind of error this is.
+    pub code: ErrorCode,
+    /// The index of the offending token, not counting trivia.
+    pub position: u32,
+    /// The tokens that would have been accepted here.
+    pub expected: TokenSet,
+    /// The token that was found instead, or `None` at the end of input.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub found: Option<TokenKind>,
+}
+
+/// The kind of a `ParseError`.
+#[derive(serde::Serialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ErrorCode {
+    /// A specific token was required.
+    ExpectedToken,
+    /// An expression was required.
+    ExpectedExpression,
+    /// A statement was required.
+    ExpectedStatement,
+    /// Statements or expressions were nested too deeply to parse.
+    NestingTooDeep,
+}
+
+/// A `ParseError` located in the source text.
+#[derive(serde::Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SyntaxError {
+    pub error: ParseError,
+    /// The range of the offending token,
+    /// or an empty range at the end of the text.
+    pub range: TextRange,
+}
+
+impl ErrorCode {
+    /// A short stable identifier for this kind of error.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::ExpectedToken => "E0001",
+            ErrorCode::ExpectedExpression => "E0002",
+            ErrorCode::ExpectedStatement => "E0003",
+            ErrorCode::NestingTooDeep => "E0004",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            ErrorCode::ExpectedToken => {
+                let expected: Vec<_> = self.expected.iter().collect();
+                match expected.as_slice() {
+                    [] => write!(f, "unexpected token")?,
+                    [kind] => write!(f, "expected {}", kind.description())?,
+                    [a, b] => write!(f, "expected {} or {}", a.description(), b.description())?,
+                    [init @ .., last] => {
+                        write!(f, "expected ")?;
+                        for kind in init {
+                            write!(f, "{}, ", kind.description())?;
+                        }
+                        write!(f, "or {}", last.description())?;
+                    }
+                }
+            }
+            ErrorCode::ExpectedExpression => write!(f, "expected an expression")?,
+            ErrorCode::ExpectedStatement => write!(f, "expected a statement")?,
+            ErrorCode::NestingTooDeep => write!(f, "nesting too deep")?,
+        }
+        match self.found {
+            Some(kind) => write!(f, ", found {}", kind.description()),
+            None => write!(f, ", found end of input"),
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for ParseError {}
+impl std::error::Error for SyntaxError {}
@@ -57,7 +57,7 @@ pub(crate) enum Event {
     /// A single terminal leaf element.
     Leaf { kind: SyntaxKind },
     /// An error at this position.
-    Error { message: ParseError },
+    Error { error: ParseError },
     /// An abandoned or already processed event that should be ignored.
     Abandoned,
 }
@@ -78,7 +78,13 @@ impl Event {
                     while let Some(fwd) = fp {
                         idx += fwd.get() as usize;
                         fp = match mem::replace(&mut events[idx], Event::Abandoned) {
-                            Event::Start { forward_parent, .. } => forward_parent,
+                            Event::Start {
+                                kind,
+                                forward_parent,
+                            } => {
+                                forward_parents.push(kind);
+                                forward_parent
+                            }
                             Event::Abandoned => None,
                             _ => unreachable!(),
                         };
@@ -89,7 +95,7 @@ impl Event {
                 }
                 Event::Finish => sink.finish(),
                 Event::Leaf { kind } => sink.leaf(kind),
-                Event::Error { message } => sink.error(message),
+                Event::Error { error } => sink.error(error),
             }
         }
     }
@@ -1,19 +1,26 @@
-pub(crate) use crate::{event::Event, parser::Parser, token_set::TokenSet};
-pub use tinyc_grammar::{SyntaxKind, TokenKind};
+pub(crate) use crate::{
+    event::Event, parser::Parser, text_token_source::TextTokenSource,
+    text_tree_sink::TextTreeSink,
+};
+pub use {
+    crate::{
+        error::{ErrorCode, ParseError, SyntaxError},
+        reparsing::TextEdit,
+    },
+    tinyc_grammar::{syntax::GreenNode, SyntaxKind, SyntaxNode, Token, TokenKind, TokenSet},
+};
 
+mod error;
 mod event;
 mod parse;
 mod parser;
-mod token_set;
-
-// TODO: structured errors
-#[derive(serde::Serialize)]
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub struct ParseError(pub String);
+mod reparsing;
+mod text_token_source;
+mod text_tree_sink;
 
 /// A source of tokens for the parser.
 ///
-/// `TokenKind::Whitespace` should be handled above this layer.
+/// Trivia (see `TokenKind::is_trivia`) should be handled above this layer.
 pub trait TokenSource {
     fn current(&self) -> Option<TokenKind> {
         self.la(0)
@@ -34,7 +41,39 @@ pub fn parse(tokens: &mut dyn TokenSource, sink: &mut dyn TreeSink) {
     Event::sink(&mut p.finish(), sink);
 }
 
-fn parse_from_tokens(tokens: &mut dyn TokenSource, f: impl FnOnce(&mut Parser)) -> Parser {
+/// The result of parsing source text: a lossless syntax tree plus any errors.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Parse {
+    green: GreenNode,
+    errors: Vec<SyntaxError>,
+}
+
+impl Parse {
+    pub fn green(&self) -> &GreenNode {
+        &self.green
+    }
+
+    pub fn syntax(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green.clone())
+    }
+
+    pub fn errors(&self) -> &[SyntaxError] {
+        &self.errors
+    }
+}
+
+/// Lex and parse `text` into a lossless syntax tree.
+///
+/// Every byte of `text` ends up in the tree: `parse_text(text).syntax().to_string() == text`.
+pub fn parse_text(text: &str) -> Parse {
+    let tokens: Vec<Token> = tinyc_lexer::tokenize(text).collect();
+    let mut token_source = TextTokenSource::new(&tokens);
+    let mut tree_sink = TextTreeSink::new(text, &tokens);
+    parse(&mut token_source, &mut tree_sink);
+    tree_sink.finish()
+}
+
+fn parse_from_tokens(tokens: &mut dyn TokenSource, f: impl FnOnce(&mut Parser)) -> Parser<'_> {
     let mut p = Parser::new(tokens);
     f(&mut p);
     p
@@ -60,35 +99,100 @@ impl TokenSource for EmptyTokenSource {
 #[cfg(test)]
 mod tests {
     use {
-        crate::{event::Event, parse, parse_from_tokens, TokenSource},
-        tinyc_lexer::{tokenize, TokenKind},
+        crate::{
+            event::Event, parse, parse_from_tokens, parse_text, SyntaxError, TextTokenSource,
+        },
+        tinyc_grammar::{
+            ast::{self, AstNode},
+            syntax::Tree,
+        },
+        tinyc_lexer::tokenize,
     };
 
-    struct VecTokenSource {
-        tokens: Vec<TokenKind>,
-        here: usize,
+    #[allow(non_snake_case)]
+    #[conformance::tests(exact, format=yaml, file="../../tests/KartikTalwar.test", section=events)]
+    fn parse_Program_events(s: &str) -> Vec<Event> {
+        let tokens: Vec<_> = tokenize(s).collect();
+        let mut tokens = TextTokenSource::new(&tokens);
+        parse_from_tokens(&mut tokens, parse::Program).finish()
     }
 
-    impl TokenSource for VecTokenSource {
-        fn la(&self, n: usize) -> Option<TokenKind> {
-            self.tokens.get(self.here + n).copied()
-        }
+    #[conformance::tests(exact, format=sexp_pretty, file="../../tests/KartikTalwar.test", section=tree)]
+    #[conformance::tests(exact, format=yaml, file="tests/trivia.yaml.test")]
+    #[conformance::tests(exact, format=sexp_pretty, file="tests/trees.sexp.test")]
+    fn parse_text_tree(s: &str) -> Tree {
+        let node = parse_text(s).syntax();
+        assert_eq!(node.to_string(), s);
+        Tree(node.into())
+    }
+
+    #[conformance::tests(superset, format=yaml, file="tests/outline.yaml.test")]
+    fn parse_text_outline(s: &str) -> Tree {
+        Tree(parse_text(s).syntax().into())
+    }
+
+    #[conformance::tests(exact, format=yaml, file="tests/errors.yaml.test")]
+    fn parse_text_errors(s: &str) -> Vec<SyntaxError> {
+        parse_text(s).errors().to_vec()
+    }
+
+    #[test]
+    fn error_messages() {
+        let messages = |text| -> Vec<String> {
+            let parse = parse_text(text);
+            parse.errors().iter().map(ToString::to_string).collect()
+        };
+        assert_eq!(messages("a=1 b=2;"), ["expected `;`, found identifier"]);
+        assert_eq!(messages("a=;"), ["expected an expression, found `;`"]);
+        assert_eq!(messages("a=1"), ["expected `;`, found end of input"]);
+    }
 
-        fn bump(&mut self) {
-            self.here += 1;
+    #[test]
+    fn lossless() {
+        for text in &[
+            "",
+            "  ",
+            "// just a comment",
+            "\n\t{ i=1; while (i<100) i=i+i; }\n",
+            "\r\n/* a */ a = /* b */ 1 ; // c\r\n",
+            "a=1; /* unterminated",
+            "a=é;",
+        ] {
+            assert_eq!(parse_text(text).syntax().to_string(), *text);
         }
     }
 
-    #[allow(non_snake_case)]
-    #[conformance::tests(exact, serde=yaml, file="tests/KartikTalwar.yaml.test")]
-    fn parse_Program_events(s: &str) -> Vec<Event> {
-        let mut tokens = VecTokenSource {
-            tokens: tokenize(s)
-                .filter(|token| token.kind != TokenKind::Whitespace)
-                .map(|token| token.kind)
-                .collect(),
-            here: 0,
+    #[test]
+    fn typed_ast() {
+        let parse = parse_text("if (a < b) c = 1; else { c = 2; }");
+        let program = ast::Program::cast(parse.syntax()).unwrap();
+        let statements: Vec<_> = program.statements().collect();
+        assert_eq!(statements.len(), 1);
+        let stmt = match &statements[0] {
+            ast::Statement::If(stmt) => stmt,
+            stmt => panic!("expected an if statement, found {:?}", stmt),
         };
-        parse_from_tokens(&mut tokens, parse::Program).finish()
+
+        let cond = match stmt.cond().unwrap().expr().unwrap() {
+            ast::Expression::Comparison(cond) => cond,
+            expr => panic!("expected a comparison, found {:?}", expr),
+        };
+        assert_eq!(cond.lhs().unwrap().syntax().to_string(), "a");
+        assert_eq!(cond.rhs().unwrap().syntax().to_string(), "b");
+
+        match stmt.then_branch().unwrap() {
+            ast::Statement::Expression(then) => match then.expr().unwrap() {
+                ast::Expression::Assignment(assign) => {
+                    assert_eq!(assign.id().unwrap().text(), "c");
+                    assert_eq!(assign.val().unwrap().syntax().to_string(), "1");
+                }
+                expr => panic!("expected an assignment, found {:?}", expr),
+            },
+            stmt => panic!("expected an expression statement, found {:?}", stmt),
+        }
+        match stmt.else_branch().unwrap() {
+            ast::Statement::Block(block) => assert_eq!(block.statements().count(), 1),
+            stmt => panic!("expected a block, found {:?}", stmt),
+        }
     }
 }
@@ -6,7 +6,7 @@ use super::*;
 pub(super) fn If(p: &mut Parser) {
     let m = p.start();
     p.bump(TokenKind::If);
-    Expression::Parenthesized(p);
+    Condition(p);
     Statement(p);
     if p.eat(TokenKind::Else) {
         Statement(p);
@@ -21,7 +21,7 @@ pub(super) fn While(p: &mut Parser) {
     assert_eq!(p.current(), Some(TokenKind::While));
     let m = p.start();
     p.bump(TokenKind::While);
-    Expression::Parenthesized(p);
+    Condition(p);
     Statement(p);
     m.complete(p, SyntaxKind::StatementWhile);
 }
@@ -32,7 +32,7 @@ pub(super) fn While(p: &mut Parser) {
 pub(super) fn Block(p: &mut Parser) {
     let m = p.start();
     p.bump(TokenKind::LeftCurlyBracket);
-    while !p.at(TokenKind::RightCurlyBracket) {
+    while p.current().is_some() && !p.at(TokenKind::RightCurlyBracket) {
         Statement(p);
     }
     p.expect(TokenKind::RightCurlyBracket);
@@ -52,3 +52,19 @@ pub(super) fn Expression(p: &mut Parser) {
     }
     m.complete(p, SyntaxKind::StatementExpression);
 }
+
+/// ```text
+/// cond:(Expression::Parenthesized)
+/// ```
+///
+/// This node emits `error` if the opening parenthesis is missing.
+fn Condition(p: &mut Parser) {
+    if p.at(TokenKind::LeftParenthesis) {
+        Expression::Parenthesized(p);
+    } else {
+        p.error(
+            ErrorCode::ExpectedToken,
+            TokenSet::new(TokenKind::LeftParenthesis),
+        );
+    }
+}
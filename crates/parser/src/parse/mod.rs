@@ -18,9 +18,9 @@
 //! Expression =
 //!   | Parenthesized:{ "(" Expression ")" }
 //!   | Assignment:{ id:Identifier "=" val:Expression }
-//!   | Comparison: #[associativity(left)] { lhs:Expression "<" rhs:Expression }
-//!   | Addition: #[associativity(left)] { lhs:Expression "+" rhs:Expression }
-//!   | Subtraction: #[associativity(left)] { lhs:Expression "-" rhs:Expression }
+//!   | Comparison: #[associativity(left), precedence(1)] { lhs:Expression "<" rhs:Expression }
+//!   | Addition: #[associativity(left), precedence(2)] { lhs:Expression "+" rhs:Expression }
+//!   | Subtraction: #[associativity(left), precedence(2)] { lhs:Expression "-" rhs:Expression }
 //!   | Term:Term
 //!   ;
 //! Term =
@@ -35,12 +35,16 @@
 //!
 //! Unless other wise noted, nodes assume their first token is present;
 //! the caller is responsible for branching on token lookahead.
+//!
+//! The parser must terminate without panicking on any input.
+//! Every loop either consumes a token or exits on end of input,
+//! and errors are reported with `Parser::error` instead.
 
 #![allow(non_snake_case)]
 
 // TODO: Inline tests
 
-use crate::{parser::CompletedMarker, Parser, SyntaxKind, TokenKind};
+use crate::{parser::CompletedMarker, ErrorCode, Parser, SyntaxKind, TokenKind, TokenSet};
 
 /// ```text
 /// Program = Statement*;
@@ -49,8 +53,16 @@ use crate::{parser::CompletedMarker, Parser, SyntaxKind, TokenKind};
 /// This node consumes the entire input.
 pub(super) fn Program(p: &mut Parser) {
     let m = p.start();
-    while p.current().is_some() {
-        Statement(p);
+    while let Some(kind) = p.current() {
+        if kind == TokenKind::RightCurlyBracket {
+            // nothing else will consume an unmatched `}`
+            let m = p.start();
+            p.error(ErrorCode::ExpectedStatement, TokenSet::STATEMENT_FIRST);
+            p.bump_any();
+            m.complete(p, SyntaxKind::ERROR);
+        } else {
+            Statement(p);
+        }
     }
     m.complete(p, SyntaxKind::Program);
 }
@@ -64,14 +76,20 @@ pub(super) fn Program(p: &mut Parser) {
 ///   ;
 /// ```
 ///
-/// This node emits `error` on unexpected leading tokens.
-fn Statement(p: &mut Parser) {
-    match p.current().unwrap() {
-        TokenKind::If => Statement::If(p),
-        TokenKind::While => Statement::While(p),
-        TokenKind::LeftCurlyBracket => Statement::Block(p),
-        _ => Statement::Expression(p),
+/// This node emits `error` on unexpected leading tokens,
+/// consuming them unless they are curly braces.
+pub(super) fn Statement(p: &mut Parser) {
+    if !p.enter() {
+        return TooDeep(p, true);
+    }
+    match p.current() {
+        Some(TokenKind::If) => Statement::If(p),
+        Some(TokenKind::While) => Statement::While(p),
+        Some(TokenKind::LeftCurlyBracket) => Statement::Block(p),
+        Some(kind) if TokenSet::STATEMENT_FIRST & kind => Statement::Expression(p),
+        _ => p.err_bump(ErrorCode::ExpectedStatement, TokenSet::STATEMENT_FIRST),
     }
+    p.exit();
 }
 mod Statement;
 
@@ -79,15 +97,22 @@ mod Statement;
 /// Expression =
 ///   | Parenthesized:{ "(" Expression ")" }
 ///   | Assignment:{ id:Identifier "=" val:Expression }
-///   | Comparison: #[associativity(left)] { lhs:Expression "<" rhs:Expression }
-///   | Addition: #[associativity(left)] { lhs:Expression "+" rhs:Expression }
-///   | Subtraction: #[associativity(left)] { lhs:Expression "-" rhs:Expression }
+///   | Comparison: #[associativity(left), precedence(1)] { lhs:Expression "<" rhs:Expression }
+///   | Addition: #[associativity(left), precedence(2)] { lhs:Expression "+" rhs:Expression }
+///   | Subtraction: #[associativity(left), precedence(2)] { lhs:Expression "-" rhs:Expression }
 ///   | Term:Term
 ///   ;
 /// ```
 ///
+/// Binary operators are parsed by precedence climbing,
+/// using the precedence table declared in `meta/syntax.toml`
+/// (see `TokenKind::binary_precedence`).
+///
 /// This node emits `error` on unexpected leading tokens.
 fn Expression(p: &mut Parser) {
+    if !p.enter() {
+        return TooDeep(p, false);
+    }
     fn Expression_(p: &mut Parser) -> CompletedMarker {
         if p.at(TokenKind::LeftParenthesis) {
             Expression::Parenthesized(p)
@@ -97,30 +122,69 @@ fn Expression(p: &mut Parser) {
             Expression::Term(p)
         }
     }
-    let mut expr = Expression_(p);
-    loop {
-        if p.at(TokenKind::LessThanSign) {
-            let m = expr.precede(p);
-            p.bump(TokenKind::LessThanSign);
-            Expression_(p);
-            expr = m.complete(p, SyntaxKind::ExpressionComparison);
-        } else if p.at(TokenKind::PlusSign) {
-            let m = expr.precede(p);
-            p.bump(TokenKind::PlusSign);
-            Expression_(p);
-            expr = m.complete(p, SyntaxKind::ExpressionAddition);
-        } else if p.at(TokenKind::HyphenMinus) {
-            let m = expr.precede(p);
-            p.bump(TokenKind::HyphenMinus);
-            Expression_(p);
-            expr = m.complete(p, SyntaxKind::ExpressionSubtraction);
-        } else {
-            break;
+    /// Parse an expression whose binary operators all bind at least as tight as `min_precedence`.
+    ///
+    /// Each operator nests the operators before it one level deeper,
+    /// so a long chain of operators counts towards the nesting depth.
+    fn Binary(p: &mut Parser, min_precedence: u8) -> CompletedMarker {
+        let mut lhs = Expression_(p);
+        let mut depth = 0;
+        while let Some((precedence, kind)) = p.current().and_then(TokenKind::binary_precedence) {
+            if precedence < min_precedence {
+                break;
+            }
+            if !p.enter() {
+                TooDeep(p, false);
+                break;
+            }
+            depth += 1;
+            let m = lhs.precede(p);
+            p.bump_any();
+            Binary(p, precedence + 1);
+            lhs = m.complete(p, kind);
+        }
+        for _ in 0..depth {
+            p.exit();
         }
+        lhs
     }
+    Binary(p, 0);
+    p.exit();
 }
 mod Expression;
 
+/// A statement or expression that is nested too deeply to parse.
+///
+/// This node emits `error` and skips to the end of the statement or expression,
+/// keeping brackets balanced so that the enclosing nodes find their closing brackets.
+/// A statement consumes at least one token unless it is at a closing curly brace.
+fn TooDeep(p: &mut Parser, statement: bool) {
+    let m = p.start();
+    p.error(ErrorCode::NestingTooDeep, TokenSet::EMPTY);
+    let mut open = 0_u32;
+    let mut empty = true;
+    while let Some(kind) = p.current() {
+        match kind {
+            TokenKind::LeftParenthesis | TokenKind::LeftCurlyBracket => open += 1,
+            TokenKind::RightParenthesis | TokenKind::RightCurlyBracket if open > 0 => open -= 1,
+            TokenKind::RightCurlyBracket => break,
+            TokenKind::RightParenthesis | TokenKind::Semicolon if !statement && open == 0 => break,
+            _ => {}
+        }
+        p.bump_any();
+        empty = false;
+        let end = matches!(kind, TokenKind::Semicolon | TokenKind::RightCurlyBracket);
+        if statement && open == 0 && end {
+            break;
+        }
+    }
+    if empty {
+        m.abandon(p);
+    } else {
+        m.complete(p, SyntaxKind::ERROR);
+    }
+}
+
 /// ```text
 /// Term =
 ///   | Identifier:(Token::Identifier)
@@ -138,7 +202,11 @@ fn Term(p: &mut Parser) {
     } else if p.at(TokenKind::LeftParenthesis) {
         Term::Expression(p)
     } else {
-        p.err_bump("expected `Integer`, `Identifier`, or `LeftParenthesis`")
+        p.err_recover(
+            ErrorCode::ExpectedExpression,
+            TokenSet::EXPRESSION_FIRST,
+            TokenSet::EXPRESSION_RECOVERY,
+        )
     }
 }
 mod Term;
@@ -1,7 +1,7 @@
 use {
-    crate::{Event, ParseError, SyntaxKind, TokenKind, TokenSet, TokenSource},
+    crate::{ErrorCode, Event, ParseError, SyntaxKind, TokenKind, TokenSet, TokenSource},
     drop_bomb::DebugDropBomb,
-    std::{cell::Cell, num::NonZeroU32},
+    std::num::NonZeroU32,
 };
 
 /// The low-level API for parsing a stream of tokens.
@@ -11,15 +11,31 @@ use {
 pub(crate) struct Parser<'tokens> {
     tokens: &'tokens mut dyn TokenSource,
     events: Vec<Event>,
+    /// The number of tokens consumed so far.
+    pos: u32,
+    /// The number of statements, expressions and binary operators currently being parsed.
+    depth: u32,
     #[cfg(debug_assertions)]
-    steps: Cell<u32>,
+    steps: std::cell::Cell<u64>,
 }
 
+/// How deeply statements and expressions may nest,
+/// where each binary operator in a chain like `a+b+c` nests the ones before it.
+/// Parsing is recursive, and so is everything that walks the tree,
+/// so anything deeper would risk overflowing the stack.
+const MAX_DEPTH: u32 = 256;
+
+/// How many lookaheads the parser may take per token consumed
+/// before it is considered stuck.
+#[cfg(debug_assertions)]
+const STEPS_PER_TOKEN: u64 = 1000;
+
 impl Parser<'_> {
     #[cfg(debug_assertions)]
     fn step(&self) {
         let steps = self.steps.get();
-        assert!(steps <= 10_000_000, "the parser seems stuck");
+        let limit = STEPS_PER_TOKEN * (u64::from(self.pos) + 1);
+        assert!(steps <= limit, "the parser seems stuck");
         self.steps.set(steps + 1);
     }
 
@@ -37,7 +53,10 @@ impl<'tokens> Parser<'tokens> {
         Parser {
             tokens,
             events: vec![],
-            steps: Cell::new(0),
+            pos: 0,
+            depth: 0,
+            #[cfg(debug_assertions)]
+            steps: std::cell::Cell::new(0),
         }
     }
 
@@ -69,7 +88,7 @@ impl<'tokens> Parser<'tokens> {
 
     /// Check if the current token is in `kinds`.
     pub(crate) fn at_any(&self, kinds: TokenSet) -> bool {
-        self.current().map_or(false, |kind| kinds & kind)
+        self.current().is_some_and(|kind| kinds & kind)
     }
 
     /// Consume the next token iff it is `kind`.
@@ -109,14 +128,20 @@ impl<'tokens> Parser<'tokens> {
     /// This is mostly useful for contextual keywords.
     pub(crate) fn bump_as(&mut self, kind: SyntaxKind) {
         self.tokens.bump();
+        self.pos += 1;
         self.push_event(Event::Leaf { kind })
     }
 
-    // TODO: structured errors
-    /// Emit an error here.
-    pub(crate) fn error(&mut self, message: impl Into<String>) {
-        let message = ParseError(message.into());
-        self.push_event(Event::Error { message })
+    /// Emit an error at the current token,
+    /// where one of the `expected` tokens would have been accepted.
+    pub(crate) fn error(&mut self, code: ErrorCode, expected: TokenSet) {
+        let error = ParseError {
+            code,
+            position: self.pos,
+            expected,
+            found: self.current(),
+        };
+        self.push_event(Event::Error { error })
     }
 
     /// Consume the next token if it is `kind`,
@@ -125,24 +150,52 @@ impl<'tokens> Parser<'tokens> {
         if self.eat(kind) {
             true
         } else {
-            self.error(format!("expected {:?}", kind));
+            self.error(ErrorCode::ExpectedToken, TokenSet::new(kind));
+            false
+        }
+    }
+
+    /// Start parsing a nested statement, expression or binary operator,
+    /// or return `false` if it would be nested too deeply.
+    ///
+    /// Each successful `enter` must be paired with an `exit`.
+    pub(crate) fn enter(&mut self) -> bool {
+        if self.depth >= MAX_DEPTH {
             false
+        } else {
+            self.depth += 1;
+            true
         }
     }
 
+    /// Finish parsing a nested statement, expression or binary operator.
+    pub(crate) fn exit(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Start at `depth`, for parsing a statement nested that deep on its own.
+    pub(crate) fn set_depth(&mut self, depth: u32) {
+        self.depth = depth;
+    }
+
     /// Create an error node and consume the next token.
-    pub(crate) fn err_bump(&mut self, message: impl Into<String>) {
-        self.err_recover(message, TokenSet::EMPTY);
+    pub(crate) fn err_bump(&mut self, code: ErrorCode, expected: TokenSet) {
+        self.err_recover(code, expected, TokenSet::EMPTY);
     }
 
     /// Create an error node and consume the next token if it is not in `recovery`.
-    pub(crate) fn err_recover(&mut self, message: impl Into<String>, mut recovery: TokenSet) {
+    pub(crate) fn err_recover(
+        &mut self,
+        code: ErrorCode,
+        expected: TokenSet,
+        mut recovery: TokenSet,
+    ) {
         // never eat curly braces during recovery
         recovery |= TokenKind::LeftCurlyBracket;
         recovery |= TokenKind::RightCurlyBracket;
 
         let m = self.start();
-        self.error(message);
+        self.error(code, expected);
         if self.at_any(recovery) {
             m.abandon(self);
         } else {
@@ -0,0 +1,384 @@
+//! Incremental reparsing after a text edit.
+//!
+//! Reparsing tries each of these in turn, using the first that applies:
+//!
+//! 1. If the edit is inside a single token, relex just that token.
+//!    This applies if it is still one token of the same kind,
+//!    and does not merge with its neighbors.
+//! 2. Reparse the smallest enclosing statement (including blocks)
+//!    whose edited text still parses as exactly one complete statement.
+//! 3. Parse the whole edited text from scratch.
+//!
+//! The result is always the same as parsing the edited text from scratch.
+
+use {
+    crate::{parse, Parse, ParseError, SyntaxError, SyntaxKind, TextTokenSource, TextTreeSink},
+    tinyc_grammar::{
+        syntax::{green_token, NodeOrToken, SyntaxNode, SyntaxToken, TextRange, TextSize},
+        Token, TokenKind,
+    },
+};
+
+/// A replacement of a range of text.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct TextEdit {
+    /// The range of the old text to remove.
+    pub delete: TextRange,
+    /// The text to insert in its place.
+    pub insert: String,
+}
+
+impl TextEdit {
+    pub fn replace(delete: TextRange, insert: impl Into<String>) -> Self {
+        TextEdit {
+            delete,
+            insert: insert.into(),
+        }
+    }
+
+    pub fn insert(offset: TextSize, insert: impl Into<String>) -> Self {
+        TextEdit::replace(TextRange::empty(offset), insert)
+    }
+
+    pub fn delete(delete: TextRange) -> Self {
+        TextEdit::replace(delete, "")
+    }
+
+    /// Apply this edit to `text`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the deleted range is not within `text`.
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        text.replace_range(std::ops::Range::<usize>::from(self.delete), &self.insert);
+        text
+    }
+
+    /// The length change of text this edit is applied to.
+    fn delta(&self) -> i64 {
+        i64::from(u32::from(TextSize::of(&*self.insert))) - i64::from(u32::from(self.delete.len()))
+    }
+
+    /// Apply this edit to a piece of text starting at `offset`
+    /// that contains the deleted range.
+    fn apply_within(&self, text: &str, offset: TextSize) -> String {
+        TextEdit::replace(self.delete - offset, &*self.insert).apply(text)
+    }
+}
+
+impl Parse {
+    /// Reparse after applying `edit` to the parsed text,
+    /// reusing as much of the existing tree as possible.
+    ///
+    /// The result is the same as `parse_text` of the edited text.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the edit's deleted range is not within the parsed text.
+    pub fn reparse(&self, edit: &TextEdit) -> Parse {
+        self.incremental_reparse(edit).unwrap_or_else(|| {
+            let text = edit.apply(&self.syntax().to_string());
+            crate::parse_text(&text)
+        })
+    }
+
+    /// Reparse without falling back to a full parse.
+    pub(crate) fn incremental_reparse(&self, edit: &TextEdit) -> Option<Parse> {
+        let root = self.syntax();
+        assert!(
+            root.text_range().contains_range(edit.delete),
+            "edit range {:?} is outside the text {:?}",
+            edit.delete,
+            root.text_range(),
+        );
+        let element = root.covering_element(edit.delete);
+        let node = match element {
+            NodeOrToken::Token(token) => match self.relex_token(&token, edit) {
+                Some(parse) => return Some(parse),
+                None => token.parent()?,
+            },
+            NodeOrToken::Node(node) => node,
+        };
+        node.ancestors()
+            .filter(|node| is_statement(node.kind()))
+            .find_map(|node| self.reparse_statement(&node, edit))
+    }
+
+    fn relex_token(&self, token: &SyntaxToken, edit: &TextEdit) -> Option<Parse> {
+        let relexable = match token.kind().as_token()? {
+            TokenKind::Identifier | TokenKind::Integer => true,
+            kind => kind.is_trivia(),
+        };
+        if !relexable {
+            return None;
+        }
+
+        let range = token.text_range();
+        let text = edit.apply_within(token.text(), range.start());
+        match relex_in_context(token.prev_token(), &text, token.next_token())?.as_slice() {
+            [new] if SyntaxKind::from(new.kind) == token.kind() => {}
+            _ => return None,
+        }
+
+        let green = token.replace_with(green_token(token.kind(), &text));
+        let new_end = range.end() + TextSize::of(&*edit.insert) - edit.delete.len();
+        let errors = self
+            .errors
+            .iter()
+            .map(|error| {
+                let mut error = error.clone();
+                if error.range.start() >= range.end() {
+                    error.range = shift(error.range, edit.delta());
+                } else if error.range == range {
+                    error.range = TextRange::new(range.start(), new_end);
+                }
+                error
+            })
+            .collect();
+        Some(Parse { green, errors })
+    }
+
+    fn reparse_statement(&self, node: &SyntaxNode, edit: &TextEdit) -> Option<Parse> {
+        let range = node.text_range();
+        let next = next_non_trivia(node);
+
+        // a statement followed by `else` might take it when reparsed
+        if next.as_ref().map(SyntaxToken::kind) == Some(SyntaxKind::Else) {
+            return None;
+        }
+        // errors at the boundaries of the statement may have come from it or from its parent
+        let boundary_after = next.map_or(self.syntax().text_range().end(), |token| {
+            token.text_range().start()
+        });
+        if self.errors.iter().any(|error| {
+            let start = error.range.start();
+            start == range.start() || start == boundary_after
+        }) {
+            return None;
+        }
+
+        let text = edit.apply_within(&node.to_string(), range.start());
+        let first = node.first_token()?;
+        let last = node.last_token()?;
+        let tokens = relex_in_context(first.prev_token(), &text, last.next_token())?;
+
+        // statements only nest inside statements, so this is how deep the parser was
+        let depth = node
+            .ancestors()
+            .skip(1)
+            .filter(|node| is_statement(node.kind()))
+            .count();
+
+        let mut complete = false;
+        let mut token_source = TextTokenSource::new(&tokens);
+        let mut tree_sink = TextTreeSink::new(&text, &tokens);
+        let p = crate::parse_from_tokens(&mut token_source, |p| {
+            p.set_depth(depth as u32);
+            let m = p.start();
+            parse::Statement(p);
+            complete = p.current().is_none();
+            m.complete(p, SyntaxKind::Program);
+        });
+        crate::Event::sink(&mut p.finish(), &mut tree_sink);
+        let sub_parse = tree_sink.finish();
+
+        // the statement must be all of the new text, with no trivia around it
+        let sub_root = sub_parse.syntax();
+        let new_node = match sub_root
+            .children_with_tokens()
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            [NodeOrToken::Node(new_node)] if is_statement(new_node.kind()) => new_node.clone(),
+            _ => return None,
+        };
+        // reaching the end of input means the statement would continue past it
+        if !complete
+            || sub_parse
+                .errors
+                .iter()
+                .any(|error| error.error.found.is_none())
+        {
+            return None;
+        }
+
+        let tokens_before = non_trivia_tokens(&self.syntax())
+            .take_while(|token| token.text_range().end() <= range.start())
+            .count() as u32;
+        let old_count = non_trivia_tokens(node).count() as i64;
+        let new_count = tokens
+            .iter()
+            .filter(|token| !token.kind.is_trivia())
+            .count() as i64;
+
+        let mut errors = vec![];
+        errors.extend(
+            self.errors
+                .iter()
+                .filter(|error| error.range.start() < range.start())
+                .cloned(),
+        );
+        errors.extend(sub_parse.errors.into_iter().map(|error| SyntaxError {
+            error: ParseError {
+                position: error.error.position + tokens_before,
+                ..error.error
+            },
+            range: error.range + range.start(),
+        }));
+        errors.extend(
+            self.errors
+                .iter()
+                .filter(|error| error.range.start() >= range.end())
+                .map(|error| SyntaxError {
+                    error: ParseError {
+                        position: (i64::from(error.error.position) + new_count - old_count) as u32,
+                        ..error.error.clone()
+                    },
+                    range: shift(error.range, edit.delta()),
+                }),
+        );
+
+        // the statement may change kind, so replace it within its parent
+        let parent = node.parent()?;
+        let new_parent = parent
+            .green()
+            .replace_child(node.index(), new_node.green().into_owned().into());
+        let green = parent.replace_with(new_parent);
+        Some(Parse { green, errors })
+    }
+}
+
+fn is_statement(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::StatementIf
+            | SyntaxKind::StatementWhile
+            | SyntaxKind::StatementBlock
+            | SyntaxKind::StatementExpression
+    )
+}
+
+fn shift(range: TextRange, delta: i64) -> TextRange {
+    let shift = |offset: TextSize| TextSize::from((i64::from(u32::from(offset)) + delta) as u32);
+    TextRange::new(shift(range.start()), shift(range.end()))
+}
+
+fn non_trivia_tokens(node: &SyntaxNode) -> impl Iterator<Item = SyntaxToken> {
+    node.descendants_with_tokens()
+        .filter_map(NodeOrToken::into_token)
+        .filter(|token| token.kind().as_token().is_none_or(|kind| !kind.is_trivia()))
+}
+
+fn next_non_trivia(node: &SyntaxNode) -> Option<SyntaxToken> {
+    let mut token = node.last_token()?.next_token();
+    while let Some(t) = token {
+        if !t.kind().as_token().is_some_and(TokenKind::is_trivia) {
+            return Some(t);
+        }
+        token = t.next_token();
+    }
+    None
+}
+
+/// Lex `text` as if it were between `prev` and `next`,
+/// or return `None` if it would merge with either of them.
+fn relex_in_context(
+    prev: Option<SyntaxToken>,
+    text: &str,
+    next: Option<SyntaxToken>,
+) -> Option<Vec<Token>> {
+    let prev_text = prev.as_ref().map_or("", |token| token.text());
+    let next_text = next.as_ref().map_or("", |token| token.text());
+    let context = format!("{}{}{}", prev_text, text, next_text);
+    let mut tokens: Vec<_> = tinyc_lexer::tokenize(&context).collect();
+
+    let same = |token: Option<&Token>, old: &SyntaxToken| {
+        token.is_some_and(|token| {
+            SyntaxKind::from(token.kind) == old.kind()
+                && TextSize::from(token.len) == old.text_range().len()
+        })
+    };
+    if let Some(next) = &next {
+        if !same(tokens.last(), next) {
+            return None;
+        }
+        tokens.pop();
+    }
+    if let Some(prev) = &prev {
+        if !same(tokens.first(), prev) {
+            return None;
+        }
+        tokens.remove(0);
+    }
+    Some(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{parse_text, TextEdit},
+        tinyc_grammar::{TextRange, TextSize},
+    };
+
+    /// Replace the first `before` in `text` with `after`, and check that reparsing
+    /// gives the same result as parsing from scratch, incrementally or not.
+    fn check(text: &str, before: &str, after: &str, incremental: bool) {
+        let start = text.find(before).unwrap();
+        let delete = TextRange::at(TextSize::from(start as u32), TextSize::of(before));
+        let edit = TextEdit::replace(delete, after);
+        let old = parse_text(text);
+        let expected = parse_text(&edit.apply(text));
+
+        let reparsed = old.incremental_reparse(&edit);
+        assert_eq!(
+            reparsed.is_some(),
+            incremental,
+            "unexpected reparse strategy for {:?}",
+            edit.apply(text),
+        );
+        if let Some(reparsed) = reparsed {
+            assert_eq!(reparsed.syntax().to_string(), expected.syntax().to_string());
+            assert_eq!(reparsed, expected);
+        }
+        assert_eq!(old.reparse(&edit), expected);
+    }
+
+    #[test]
+    fn relex_token() {
+        check("x=ab;", "ab", "cd", true);
+        check("x=12;", "1", "9", true);
+        check("a=1;  b=2;", "  ", " \n ", true);
+        check("a=1; // hi\nb=2;", "hi", "there", true);
+        check("a=1; /* hi */ b=2;", "hi", "there", true);
+        // errors after the edit are shifted
+        check("a=; b=2; c=;", "2", "22", true);
+        // the token changes kind
+        check("x=i;", "i", "if", false);
+        // the token disappears, so its statement is reparsed instead
+        check("a=1 ; b=2;", " ", "", true);
+        // the edit is not within one token or statement
+        check("a=1; b=2;", "1; b", "1;b", false);
+    }
+
+    #[test]
+    fn reparse_statement() {
+        check("{ a=1; b=2; }", "2", "2+3", true);
+        check("while (a) { b=1; }", "b=1;", "b=1; c=2;", true);
+        check("a=1; b=2; c=;", "1", "1+1", true);
+        check("a=; b=1; c=;", "b=1;", "{ b=1 }", true);
+        // a new `if` would take the `else`, so the enclosing `if` is reparsed
+        check("if (a) b=1; else c=2;", "b=1;", "if (d) b=1;", true);
+        // the statement would continue past its end
+        check("a=1; b=2;", "1;", "1", false);
+        check("a=1; b=2;", "a=1;", "a=1; {", false);
+        // the statement would merge with the `else` before it
+        check("if (a) b; else(c);", "(c)", "x(c)", false);
+        // errors at the end of the statement might be from the statement
+        check("a=1 b=2;", "a=1", "a=1;", false);
+        // statements too deeply nested to parse stay that way
+        let deep = format!("{}a=1;{}", "{".repeat(300), "}".repeat(300));
+        check(&deep, "1", "2", true);
+        check(&deep, "a=1;", "if (a) b;", true);
+    }
+}
@@ -0,0 +1,30 @@
+use crate::{Token, TokenKind, TokenSource};
+
+/// A `TokenSource` over lexed source text, skipping trivia.
+pub(crate) struct TextTokenSource {
+    tokens: Vec<TokenKind>,
+    here: usize,
+}
+
+impl TextTokenSource {
+    pub(crate) fn new(tokens: &[Token]) -> Self {
+        TextTokenSource {
+            tokens: tokens
+                .iter()
+                .map(|token| token.kind)
+                .filter(|kind| !kind.is_trivia())
+                .collect(),
+            here: 0,
+        }
+    }
+}
+
+impl TokenSource for TextTokenSource {
+    fn la(&self, n: usize) -> Option<TokenKind> {
+        self.tokens.get(self.here + n).copied()
+    }
+
+    fn bump(&mut self) {
+        self.here += 1;
+    }
+}
@@ -0,0 +1,110 @@
+use {
+    crate::{Parse, ParseError, SyntaxError, SyntaxKind, Token, TreeSink},
+    tinyc_grammar::syntax::{GreenNodeBuilder, TextRange, TextSize},
+};
+
+/// A `TreeSink` that builds a lossless syntax tree.
+///
+/// The parser never sees trivia, so this sink walks the full token list
+/// alongside the parser's events and attaches any trivia it skipped over
+/// to the node currently being built. Trivia before the first
+/// and after the last token ends up in the root node.
+pub(crate) struct TextTreeSink<'a> {
+    text: &'a str,
+    tokens: &'a [Token],
+    text_pos: usize,
+    token_pos: usize,
+    depth: usize,
+    inner: GreenNodeBuilder,
+    /// The ranges of the non-trivia tokens, which `ParseError::position` indexes.
+    ranges: Vec<TextRange>,
+    errors: Vec<SyntaxError>,
+}
+
+impl<'a> TextTreeSink<'a> {
+    pub(crate) fn new(text: &'a str, tokens: &'a [Token]) -> Self {
+        let mut offset = TextSize::from(0);
+        let ranges = tokens
+            .iter()
+            .filter_map(|token| {
+                let range = TextRange::at(offset, token.len.into());
+                offset = range.end();
+                Some(range).filter(|_| !token.kind.is_trivia())
+            })
+            .collect();
+        TextTreeSink {
+            text,
+            tokens,
+            text_pos: 0,
+            token_pos: 0,
+            depth: 0,
+            inner: GreenNodeBuilder::new(),
+            ranges,
+            errors: vec![],
+        }
+    }
+
+    pub(crate) fn finish(self) -> Parse {
+        Parse {
+            green: self.inner.finish(),
+            errors: self.errors,
+        }
+    }
+
+    fn eat_trivia(&mut self) {
+        while let Some(token) = self.tokens.get(self.token_pos) {
+            if !token.kind.is_trivia() {
+                break;
+            }
+            self.do_token(token.kind.into());
+        }
+    }
+
+    fn eat_remaining(&mut self) {
+        while let Some(token) = self.tokens.get(self.token_pos) {
+            self.do_token(token.kind.into());
+        }
+    }
+
+    fn do_token(&mut self, kind: SyntaxKind) {
+        let len = self.tokens[self.token_pos].len as usize;
+        let text = &self.text[self.text_pos..self.text_pos + len];
+        self.inner.token(kind, text);
+        self.text_pos += len;
+        self.token_pos += 1;
+    }
+}
+
+impl TreeSink for TextTreeSink<'_> {
+    fn leaf(&mut self, kind: SyntaxKind) {
+        self.eat_trivia();
+        self.do_token(kind);
+    }
+
+    fn start(&mut self, kind: SyntaxKind) {
+        if self.depth == 0 {
+            self.inner.start_node(kind);
+            self.eat_trivia();
+        } else {
+            self.eat_trivia();
+            self.inner.start_node(kind);
+        }
+        self.depth += 1;
+    }
+
+    fn finish(&mut self) {
+        self.depth -= 1;
+        if self.depth == 0 {
+            self.eat_remaining();
+        }
+        self.inner.finish_node();
+    }
+
+    fn error(&mut self, error: ParseError) {
+        let range = match self.ranges.get(error.position as usize) {
+            Some(&range) => range,
+            None => TextRange::empty(TextSize::of(self.text)),
+        };
+        self.errors.push(SyntaxError { error, range });
+    }
+}
@@ -0,0 +1,172 @@
+//! Property tests that the parser terminates without panicking on any input,
+//! and that reparsing after an edit agrees with parsing from scratch.
+
+use {
+    proptest::{
+        collection::vec,
+        prelude::*,
+        sample::{select, Index},
+    },
+    tinyc_grammar::{TextRange, TextSize},
+    tinyc_parser::{
+        parse, parse_text, ErrorCode, ParseError, SyntaxKind, TextEdit, TokenKind, TokenSource,
+        TreeSink,
+    },
+};
+
+/// Snippets that are likely to combine into interesting (broken) programs.
+const FRAGMENTS: &[&str] = &[
+    "if", "while", "else", "{", "}", "(", ")", "+", "-", "<", ";", "=", "a", "b", "1", "23",
+    " ", "\n", "\r\n", "//", "/*", "*/", "é", "@",
+];
+
+fn source() -> impl Strategy<Value = String> {
+    vec(select(FRAGMENTS), 0..64).prop_map(|fragments| fragments.concat())
+}
+
+/// A valid chain of binary operators, long enough to nest deeper than the parser allows.
+fn chain() -> impl Strategy<Value = String> {
+    let operator = select(&["+", "-", "<", " + "][..]);
+    let operand = select(&["a", "1", "(a)", "(a+b)", "b=a"][..]);
+    vec((operator, operand), 0..1024).prop_map(|operations| {
+        let mut text = String::from("a=a");
+        for (operator, operand) in operations {
+            text.push_str(operator);
+            text.push_str(operand);
+        }
+        text + ";"
+    })
+}
+
+/// An edit of `text` at character boundaries.
+fn edit(text: &str, start: Index, end: Index, insert: Vec<&str>) -> TextEdit {
+    let boundaries: Vec<_> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(Some(text.len()))
+        .collect();
+    let start = start.get(&boundaries);
+    let end = end.get(&boundaries);
+    let (start, end) = (*start.min(end), *start.max(end));
+    let delete = TextRange::new(TextSize::from(start as u32), TextSize::from(end as u32));
+    TextEdit::replace(delete, insert.concat())
+}
+
+struct VecTokenSource {
+    tokens: Vec<TokenKind>,
+    here: usize,
+}
+
+impl TokenSource for VecTokenSource {
+    fn la(&self, n: usize) -> Option<TokenKind> {
+        self.tokens.get(self.here + n).copied()
+    }
+
+    fn bump(&mut self) {
+        self.here += 1;
+    }
+}
+
+/// Checks that the tree is well formed.
+#[derive(Default)]
+struct CheckingTreeSink {
+    depth: usize,
+    roots: usize,
+    leaves: usize,
+}
+
+impl TreeSink for CheckingTreeSink {
+    fn leaf(&mut self, _kind: SyntaxKind) {
+        assert!(self.depth > 0, "leaf outside of the root node");
+        self.leaves += 1;
+    }
+
+    fn start(&mut self, _kind: SyntaxKind) {
+        if self.depth == 0 {
+            self.roots += 1;
+        }
+        self.depth += 1;
+    }
+
+    fn finish(&mut self) {
+        assert!(self.depth > 0, "unbalanced finish");
+        self.depth -= 1;
+    }
+
+    fn error(&mut self, _error: ParseError) {}
+}
+
+proptest! {
+    #[test]
+    fn parse_text_is_lossless(text in source()) {
+        let parse = parse_text(&text);
+        prop_assert_eq!(parse.syntax().to_string(), text.as_str());
+        for error in parse.errors() {
+            prop_assert!(usize::from(error.range.end()) <= text.len());
+        }
+    }
+
+    #[test]
+    fn parse_text_chain(text in chain()) {
+        let parse = parse_text(&text);
+        prop_assert_eq!(parse.syntax().to_string(), text.as_str());
+        for error in parse.errors() {
+            prop_assert_eq!(error.error.code, ErrorCode::NestingTooDeep);
+        }
+    }
+
+    #[test]
+    fn parse_text_any_string(text in any::<String>()) {
+        let parse = parse_text(&text);
+        prop_assert_eq!(parse.syntax().to_string(), text.as_str());
+    }
+
+    #[test]
+    fn reparse_is_parse(
+        text in source(),
+        start in any::<Index>(),
+        end in any::<Index>(),
+        insert in vec(select(FRAGMENTS), 0..4),
+    ) {
+        let edit = edit(&text, start, end, insert);
+        let reparsed = parse_text(&text).reparse(&edit);
+        prop_assert_eq!(reparsed, parse_text(&edit.apply(&text)));
+    }
+
+    #[test]
+    fn parse_any_tokens(tokens in vec(select(TokenKind::ALL), 0..64)) {
+        let len = tokens.len();
+        let mut source = VecTokenSource { tokens, here: 0 };
+        let mut sink = CheckingTreeSink::default();
+        parse(&mut source, &mut sink);
+        prop_assert_eq!(sink.depth, 0);
+        prop_assert_eq!(sink.roots, 1);
+        prop_assert_eq!(sink.leaves, len);
+    }
+}
+
+#[test]
+fn unterminated() {
+    for text in &["{", "{ a;", "if", "if (", "if (a", "while (a) {", "(", "a=", "a=(b", "}", ")"] {
+        let parse = parse_text(text);
+        assert_eq!(parse.syntax().to_string(), *text);
+        assert!(!parse.errors().is_empty(), "{:?} should have errors", text);
+    }
+}
+
+#[test]
+fn deeply_nested() {
+    const DEPTH: usize = 100_000;
+    let parens = format!("a={}a{};", "(".repeat(DEPTH), ")".repeat(DEPTH));
+    let blocks = format!("{}a;{}", "{".repeat(DEPTH), "}".repeat(DEPTH));
+    let ifs = format!("{}a;", "if(a)".repeat(DEPTH));
+    let chain = format!("a=a{};", "+a".repeat(DEPTH));
+    for text in &[parens, blocks, ifs, chain] {
+        let parse = parse_text(text);
+        assert_eq!(parse.syntax().to_string(), *text);
+        assert!(!parse.errors().is_empty());
+        for error in parse.errors() {
+            assert_eq!(error.error.code, ErrorCode::NestingTooDeep);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+{%- for node in nodes %}
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct {{ node.name | camel_case }} {
+    syntax: SyntaxNode,
+}
+
+impl AstNode for {{ node.name | camel_case }} {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        kind == SyntaxKind::{{ node.name | camel_case }}
+    }
+
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some({{ node.name | camel_case }} { syntax })
+        } else {
+            None
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
+{%- if node.fields %}
+
+#[allow(missing_docs)]
+impl {{ node.name | camel_case }} {
+    {%- for field in node.fields %}
+    {%- if field.kind == "token" %}
+    pub fn {{ field.name | snake_case }}(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, SyntaxKind::{{ field.ty | camel_case }}, {{ field.index }})
+    }
+    {%- elif field.kind == "node" %}
+    pub fn {{ field.name | snake_case }}(&self) -> Option<{{ field.ty | camel_case }}> {
+        support::child(
+            &self.syntax,
+            {% if field.after %}Some(SyntaxKind::{{ field.after | camel_case }}){% else %}None{% endif %},
+            {% if field.before %}Some(SyntaxKind::{{ field.before | camel_case }}){% else %}None{% endif %},
+            {{ field.index }},
+        )
+    }
+    {%- else %}
+    pub fn {{ field.name | snake_case }}(&self) -> AstChildren<{{ field.ty | camel_case }}> {
+        support::children(&self.syntax)
+    }
+    {%- endif %}
+    {%- endfor %}
+}
+{%- endif %}
+{% endfor %}
+{%- for category in categories %}
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum {{ category.name | camel_case }} {
+    {%- for variant in category.variants %}
+    {{ variant | camel_case }}({{ category.name | camel_case }}{{ variant | camel_case }}),
+    {%- endfor %}
+}
+
+impl AstNode for {{ category.name | camel_case }} {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        matches!(
+            kind,
+            {% for variant in category.variants -%}
+            | SyntaxKind::{{ category.name | camel_case }}{{ variant | camel_case }}
+            {% endfor -%}
+        )
+    }
+
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        match syntax.kind() {
+            {%- for variant in category.variants %}
+            SyntaxKind::{{ category.name | camel_case }}{{ variant | camel_case }} => {
+                Some({{ category.name | camel_case }}::{{ variant | camel_case }}({{ category.name | camel_case }}{{ variant | camel_case }} { syntax }))
+            }
+            {%- endfor %}
+            _ => None,
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            {%- for variant in category.variants %}
+            {{ category.name | camel_case }}::{{ variant | camel_case }}(it) => it.syntax(),
+            {%- endfor %}
+        }
+    }
+}
+{%- for variant in category.variants %}
+
+impl From<{{ category.name | camel_case }}{{ variant | camel_case }}> for {{ category.name | camel_case }} {
+    fn from(node: {{ category.name | camel_case }}{{ variant | camel_case }}) -> Self {
+        {{ category.name | camel_case }}::{{ variant | camel_case }}(node)
+    }
+}
+{%- endfor %}
+{% endfor %}
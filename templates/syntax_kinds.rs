@@ -1,7 +1,7 @@
 #[repr(u16)]
 #[allow(missing_docs)]
 #[derive(serde::Serialize, serde::Deserialize)]
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum SyntaxKind {
     {%- for kind in terminals %}
     {{ kind | camel_case }},
@@ -55,18 +55,12 @@ impl SyntaxKind {
 impl SyntaxKind {
     {%- for kind in all_kinds %}
     pub fn is_{{ kind | snake_case }}(self) -> bool {
-        match self {
-            SyntaxKind::{{ kind | camel_case }} => true,
-            _ => false,
-        }
+        matches!(self, SyntaxKind::{{ kind | camel_case }})
     }
     {%- endfor %}
     #[allow(non_snake_case)]
     pub fn is_ERROR(self) -> bool {
-        match self {
-            SyntaxKind::ERROR => true,
-            _ => false,
-        }
+        matches!(self, SyntaxKind::ERROR)
     }
 }
 
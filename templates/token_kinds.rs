@@ -13,29 +13,49 @@ pub enum TokenKind {
 #[allow(missing_docs)]
 impl TokenKind {
     pub fn is_keyword(self) -> bool {
-        match self {
+        matches!(
+            self,
             {% for keyword in keywords -%}
             | TokenKind::{{ keyword | camel_case }}
-            {% endfor -%} => true,
-            _ => false,
-        }
+            {% endfor -%}
+        )
     }
 
     pub fn is_literal(self) -> bool {
-        match self {
+        matches!(
+            self,
             {% for literal in literals -%}
             | TokenKind::{{ literal | camel_case }}
-            {% endfor -%} => true,
-            _ => false,
-        }
+            {% endfor -%}
+        )
     }
 
     pub fn is_punctuation(self) -> bool {
-        match self {
+        matches!(
+            self,
             {% for punct in punctuation -%}
             | TokenKind::{{ punct.name | camel_case }}
-            {% endfor -%} => true,
-            _ => false,
+            {% endfor -%}
+        )
+    }
+
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            {% for trivia in trivia -%}
+            | TokenKind::{{ trivia | camel_case }}
+            {% endfor -%}
+        )
+    }
+
+    /// The precedence and resulting node kind of this token as a binary operator.
+    /// Higher precedence binds tighter; all binary operators are left associative.
+    pub fn binary_precedence(self) -> Option<(u8, SyntaxKind)> {
+        match self {
+            {%- for op in binary_operators %}
+            TokenKind::{{ op.token | camel_case }} => Some(({{ op.precedence }}, SyntaxKind::{{ op.node | camel_case }})),
+            {%- endfor %}
+            _ => None,
         }
     }
 
@@ -72,27 +92,82 @@ impl TokenKind {
     pub const fn name(self) -> &'static str {
         TokenKind::NAMES[self as u16 as usize]
     }
+
+    /// How this kind of token reads in a message:
+    /// the text of a keyword or punctuation in backticks, or else what kind of token it is.
+    pub const fn description(self) -> &'static str {
+        match self {
+            {%- for keyword in keywords %}
+            TokenKind::{{ keyword | camel_case }} => "`{{ keyword }}`",
+            {%- endfor %}
+            {%- for punct in punctuation %}
+            TokenKind::{{ punct.name | camel_case }} => "`{{ punct.character }}`",
+            {%- endfor %}
+            {%- for kind in literals %}
+            TokenKind::{{ kind | camel_case }} => "{{ kind }}",
+            {%- endfor %}
+            {%- for kind in tokens %}
+            TokenKind::{{ kind | camel_case }} => "{{ kind }}",
+            {%- endfor %}
+            {%- for kind in trivia %}
+            TokenKind::{{ kind | camel_case }} => "{{ kind }}",
+            {%- endfor %}
+            TokenKind::ERROR => "unrecognized character",
+        }
+    }
 }
 
 #[allow(missing_docs)]
 impl TokenKind {
     {%- for kind in terminals %}
     pub fn is_{{ kind | snake_case }}(self) -> bool {
-        match self {
-            TokenKind::{{ kind | camel_case }} => true,
-            _ => false,
-        }
+        matches!(self, TokenKind::{{ kind | camel_case }})
     }
     {%- endfor %}
     #[allow(non_snake_case)]
     pub fn is_ERROR(self) -> bool {
-        match self {
-            TokenKind::ERROR => true,
-            _ => false,
-        }
+        matches!(self, TokenKind::ERROR)
     }
 }
 
+/// The groups of tokens in `meta/syntax.toml`, and the sets declared there.
+#[allow(missing_docs)]
+impl TokenSet {
+    pub const KEYWORDS: TokenSet = TokenSet::from_kinds(&[
+        {%- for keyword in keywords %}
+        TokenKind::{{ keyword | camel_case }},
+        {%- endfor %}
+    ]);
+
+    pub const LITERALS: TokenSet = TokenSet::from_kinds(&[
+        {%- for literal in literals %}
+        TokenKind::{{ literal | camel_case }},
+        {%- endfor %}
+    ]);
+
+    pub const PUNCTUATION: TokenSet = TokenSet::from_kinds(&[
+        {%- for punct in punctuation %}
+        TokenKind::{{ punct.name | camel_case }},
+        {%- endfor %}
+    ]);
+
+    pub const TRIVIA: TokenSet = TokenSet::from_kinds(&[
+        {%- for trivia in trivia %}
+        TokenKind::{{ trivia | camel_case }},
+        {%- endfor %}
+    ]);
+    {% for set in token_sets %}
+    pub const {{ set.name | shouty_snake_case }}: TokenSet = TokenSet::from_kinds(&[
+        {%- for kind in set.kinds %}
+        TokenKind::{{ kind | camel_case }},
+        {%- endfor %}
+    ])
+    {%- for other in set.sets %}
+    .union(TokenSet::{{ other | shouty_snake_case }})
+    {%- endfor %};
+    {%- endfor %}
+}
+
 impl From<TokenKind> for u16 {
     fn from(kind: TokenKind) -> u16 {
         kind as u16